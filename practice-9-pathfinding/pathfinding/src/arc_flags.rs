@@ -0,0 +1,285 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+
+use crate::graph::{point_to_point, Graph};
+use crate::queue::{DefaultQueue, PriorityQueue};
+use crate::{get_byte_array_from_u32, get_u32_from_byte_array, Map, SearchResult};
+
+/// Marks the flag files written by `ArcFlags::to_bytes`.
+const FILE_MAGIC: &[u8; 4] = b"ARC1";
+const HEADER_LENGTH: usize = 20;
+/// One bit per cell in a u64 for every edge.
+pub const MAX_CELLS: usize = 64;
+
+/// Splits the nodes into `rows` bands of about the same number of nodes by latitude, then
+/// every band into `columns` cells by longitude. Cells follow the density of the road network
+/// instead of a fixed grid, so Oslo does not share a cell with half of Finnmark.
+pub fn grid_partition(map: &Map, rows: usize, columns: usize) -> Vec<u8> {
+    assert!(rows * columns <= MAX_CELLS, "At most {} cells", MAX_CELLS);
+    let mut nodes: Vec<usize> = (0..map.nodes.len()).collect();
+    nodes.sort_by(|a, b| map.nodes[*a].latitude.total_cmp(&map.nodes[*b].latitude));
+    let mut cells = vec![0; map.nodes.len()];
+    let band_size = nodes.len().div_ceil(rows).max(1);
+    for (row, band) in nodes.chunks_mut(band_size).enumerate() {
+        band.sort_by(|a, b| map.nodes[*a].longitude.total_cmp(&map.nodes[*b].longitude));
+        let cell_size = band.len().div_ceil(columns).max(1);
+        for (column, cell) in band.chunks(cell_size).enumerate() {
+            for &node in cell {
+                cells[node] = (row * columns + column) as u8;
+            }
+        }
+    }
+    cells
+}
+
+/// Arc flags (Lauther, 2004) over a partition of the nodes into at most 64 cells.
+///
+/// Bit `c` of an edge is set if the edge starts a shortest path to some node in cell `c`, so
+/// a search towards that cell can skip every edge without the bit. The flags come from one
+/// backward search from every boundary node of every cell, a node with an edge coming in from
+/// another cell.
+pub struct ArcFlags {
+    rows: usize,
+    columns: usize,
+    cells: Vec<u8>,
+    /// Index of the first edge of every node in `flags`, like the edges are stored in the map.
+    offsets: Vec<usize>,
+    flags: Vec<u64>,
+}
+
+/// Arrays for the backward searches of one thread, reset after each search.
+struct TreeSearch {
+    distances: Vec<u32>,
+    /// Edge every reached node takes towards the boundary node.
+    tree_edges: Vec<u32>,
+    touched: Vec<usize>,
+    priority_queue: DefaultQueue,
+}
+
+impl TreeSearch {
+    fn new(node_count: usize) -> Self {
+        Self {
+            distances: vec![u32::MAX; node_count],
+            tree_edges: vec![0; node_count],
+            touched: Vec::new(),
+            priority_queue: DefaultQueue::new(node_count),
+        }
+    }
+
+    /// Backward Dijkstra from `boundary`, setting `bit` on the edge every reached node uses on
+    /// its way there.
+    fn flag_tree(
+        &mut self,
+        incoming: &[Vec<(u32, u32, u32)>],
+        boundary: usize,
+        bit: u64,
+        flags: &[AtomicU64],
+    ) {
+        self.distances[boundary] = 0;
+        self.touched.push(boundary);
+        self.priority_queue.push(boundary, 0);
+        while let Some((number, distance)) = self.priority_queue.pop() {
+            if number != boundary {
+                flags[self.tree_edges[number] as usize].fetch_or(bit, Ordering::Relaxed);
+            }
+            for &(from, edge, drive_time) in &incoming[number] {
+                let from = from as usize;
+                let alt = distance.saturating_add(drive_time);
+                if alt < self.distances[from] {
+                    if self.distances[from] == u32::MAX {
+                        self.touched.push(from);
+                    }
+                    self.distances[from] = alt;
+                    self.tree_edges[from] = edge;
+                    self.priority_queue.push(from, alt);
+                }
+            }
+        }
+        for node in self.touched.drain(..) {
+            self.distances[node] = u32::MAX;
+        }
+    }
+}
+
+impl ArcFlags {
+    /// Computes the flags on at most `workers` threads. `progress` is called with the number
+    /// of boundary nodes done and the total.
+    pub fn build(
+        map: &Map,
+        rows: usize,
+        columns: usize,
+        workers: usize,
+        progress: impl Fn(usize, usize) + Sync,
+    ) -> Self {
+        let cells = grid_partition(map, rows, columns);
+        let mut offsets = Vec::with_capacity(map.edges.len() + 1);
+        offsets.push(0);
+        for edges in &map.edges {
+            offsets.push(offsets[offsets.len() - 1] + edges.len());
+        }
+
+        // (from, edge index, drive time) of every edge into a node
+        let mut incoming = vec![Vec::new(); map.edges.len()];
+        let mut boundaries = Vec::new();
+        for (from, edges) in map.edges.iter().enumerate() {
+            for (i, edge) in edges.iter().enumerate() {
+                let index = (offsets[from] + i) as u32;
+                incoming[edge.to as usize].push((from as u32, index, edge.drive_time));
+                if cells[from] != cells[edge.to as usize] {
+                    boundaries.push(edge.to as usize);
+                }
+            }
+        }
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        // Edges inside a cell lead to it without any search
+        let flags: Vec<AtomicU64> = map
+            .edges
+            .iter()
+            .flat_map(|edges| {
+                edges
+                    .iter()
+                    .map(|e| AtomicU64::new(1 << cells[e.to as usize]))
+            })
+            .collect();
+        let next_boundary = AtomicUsize::new(0);
+        let done = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for _ in 0..workers.clamp(1, boundaries.len().max(1)) {
+                scope.spawn(|| {
+                    let mut search = TreeSearch::new(map.edges.len());
+                    loop {
+                        let index = next_boundary.fetch_add(1, Ordering::Relaxed);
+                        let Some(&boundary) = boundaries.get(index) else {
+                            break;
+                        };
+                        search.flag_tree(&incoming, boundary, 1 << cells[boundary], &flags);
+                        progress(done.fetch_add(1, Ordering::Relaxed) + 1, boundaries.len());
+                    }
+                });
+            }
+        });
+
+        Self {
+            rows,
+            columns,
+            cells,
+            offsets,
+            flags: flags.into_iter().map(AtomicU64::into_inner).collect(),
+        }
+    }
+
+    /// (rows, columns) of the partition.
+    pub fn grid(&self) -> (usize, usize) {
+        (self.rows, self.columns)
+    }
+
+    /// Share of the (edge, cell) flags that are set. The lower, the more a search can skip.
+    pub fn flag_density(&self) -> f64 {
+        let set: usize = self.flags.iter().map(|f| f.count_ones() as usize).sum();
+        set as f64 / (self.flags.len() * self.rows * self.columns).max(1) as f64
+    }
+
+    /// Magic, rows, columns, node and edge count, big-endian like the other files. Then the
+    /// cell of every node and the flags of every edge, one byte per eight cells.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let flag_size = (self.rows * self.columns).div_ceil(8);
+        let mut bytes =
+            Vec::with_capacity(HEADER_LENGTH + self.cells.len() + self.flags.len() * flag_size);
+        bytes.extend(FILE_MAGIC);
+        bytes.extend(get_byte_array_from_u32(self.rows as u32));
+        bytes.extend(get_byte_array_from_u32(self.columns as u32));
+        bytes.extend(get_byte_array_from_u32(self.cells.len() as u32));
+        bytes.extend(get_byte_array_from_u32(self.flags.len() as u32));
+        bytes.extend(&self.cells);
+        for flag in &self.flags {
+            bytes.extend(&flag.to_be_bytes()[8 - flag_size..]);
+        }
+        bytes
+    }
+
+    /// Returns None if the bytes are not a complete flag file for the map.
+    pub fn from_bytes(map: &Map, bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < HEADER_LENGTH || &bytes[0..4] != FILE_MAGIC {
+            return None;
+        }
+        let rows = get_u32_from_byte_array(&bytes[4..8]) as usize;
+        let columns = get_u32_from_byte_array(&bytes[8..12]) as usize;
+        let node_count = get_u32_from_byte_array(&bytes[12..16]) as usize;
+        let edge_count = get_u32_from_byte_array(&bytes[16..20]) as usize;
+        let cell_count = rows * columns;
+        let flag_size = cell_count.div_ceil(8);
+        if cell_count == 0
+            || cell_count > MAX_CELLS
+            || node_count != map.edges.len()
+            || edge_count != map.edges.iter().map(Vec::len).sum::<usize>()
+            || bytes.len() != HEADER_LENGTH + node_count + edge_count * flag_size
+        {
+            return None;
+        }
+
+        let cells = bytes[HEADER_LENGTH..HEADER_LENGTH + node_count].to_vec();
+        if cells.iter().any(|cell| *cell as usize >= cell_count) {
+            return None;
+        }
+        let flags = bytes[HEADER_LENGTH + node_count..]
+            .chunks_exact(flag_size)
+            .map(|chunk| {
+                let mut flag = [0; 8];
+                flag[8 - flag_size..].copy_from_slice(chunk);
+                u64::from_be_bytes(flag)
+            })
+            .collect();
+        let mut offsets = Vec::with_capacity(map.edges.len() + 1);
+        offsets.push(0);
+        for edges in &map.edges {
+            offsets.push(offsets[offsets.len() - 1] + edges.len());
+        }
+        Some(Self {
+            rows,
+            columns,
+            cells,
+            offsets,
+            flags,
+        })
+    }
+}
+
+/// The map without the edges that are not flagged for one cell.
+struct FlaggedFor<'a> {
+    map: &'a Map,
+    arc_flags: &'a ArcFlags,
+    bit: u64,
+}
+
+impl Graph for FlaggedFor<'_> {
+    fn node_count(&self) -> usize {
+        self.map.edges.len()
+    }
+
+    fn neighbours(&self, node: usize) -> impl Iterator<Item = (usize, u32)> + '_ {
+        let flags = &self.arc_flags.flags[self.arc_flags.offsets[node]..];
+        self.map.edges[node]
+            .iter()
+            .zip(flags)
+            .filter(|(_, flag)| *flag & self.bit != 0)
+            .map(|(edge, _)| (edge.to as usize, edge.drive_time))
+    }
+}
+
+/// Dijkstra that only follows edges flagged for the goal's cell. Returns None if the goal can
+/// not be reached.
+pub fn arc_flags_dijkstra(
+    map: &Map,
+    arc_flags: &ArcFlags,
+    from: usize,
+    to: usize,
+) -> Option<SearchResult> {
+    let graph = FlaggedFor {
+        map,
+        arc_flags,
+        bit: 1 << arc_flags.cells[to],
+    };
+    point_to_point::<DefaultQueue>(&graph, from, to, |_| 0)
+}
@@ -0,0 +1,52 @@
+use crate::geo::haversine_distance;
+use crate::graph::point_to_point;
+use crate::queue::DefaultQueue;
+use crate::{Map, SearchResult};
+
+/// Lower bound on the drive time between two nodes from the great-circle distance between them,
+/// for A* without any preprocessing.
+pub struct StraightLine<'a> {
+    map: &'a Map,
+    /// Lowest drive time per meter of straight line of any edge, so no route can beat it.
+    centi_seconds_per_meter: f64,
+}
+
+impl<'a> StraightLine<'a> {
+    /// Looks at every edge once. Edges without drive time make the bound 0, which is Dijkstra.
+    pub fn new(map: &'a Map) -> Self {
+        let mut centi_seconds_per_meter = f64::MAX;
+        for (from, edges) in map.edges.iter().enumerate() {
+            let (latitude, longitude) = map.get_coordinates_from_node(from);
+            for edge in edges {
+                let (to_latitude, to_longitude) = map.get_coordinates_from_node(edge.to as usize);
+                let meters = haversine_distance(latitude, longitude, to_latitude, to_longitude);
+                if meters > 0.0 {
+                    centi_seconds_per_meter =
+                        centi_seconds_per_meter.min(edge.drive_time as f64 / meters);
+                }
+            }
+        }
+        if centi_seconds_per_meter == f64::MAX {
+            centi_seconds_per_meter = 0.0;
+        }
+        Self {
+            map,
+            // Rounding in the distances must not make the bound overestimate
+            centi_seconds_per_meter: centi_seconds_per_meter * 0.999_999,
+        }
+    }
+
+    /// Never more than the drive time from `node` to `goal`, and never drops by more than the
+    /// drive time of an edge along it, so A* settles every node once.
+    pub fn lower_bound(&self, node: usize, goal: usize) -> u32 {
+        let (latitude, longitude) = self.map.get_coordinates_from_node(node);
+        let (goal_latitude, goal_longitude) = self.map.get_coordinates_from_node(goal);
+        let meters = haversine_distance(latitude, longitude, goal_latitude, goal_longitude);
+        (meters * self.centi_seconds_per_meter) as u32
+    }
+}
+
+/// A* with straight-line lower bounds. Returns None if the goal can not be reached.
+pub fn astar(map: &Map, bound: &StraightLine, source: usize, goal: usize) -> Option<SearchResult> {
+    point_to_point::<DefaultQueue>(map, source, goal, |node| bound.lower_bound(node, goal))
+}
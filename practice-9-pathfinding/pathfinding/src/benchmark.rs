@@ -0,0 +1,302 @@
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::ControlFlow;
+use std::time::Instant;
+
+use crate::components::strongly_connected_components;
+use crate::graph::Search;
+use crate::{format_number, Map};
+
+/// Xorshift64* generator, so the same seed gives the same queries on every machine.
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        // The state must never be zero
+        Self((seed ^ 0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform number in `0..bound`.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (((self.next_u64() >> 32) * bound as u64) >> 32) as usize
+    }
+}
+
+pub struct Query {
+    pub source: u32,
+    pub target: u32,
+    /// "random", or "rank 2^k" for a target that is the 2^k-th node settled from the source.
+    pub set: String,
+}
+
+/// Nodes of the largest strongly connected component, so every query between them has a route.
+pub fn largest_component_nodes(map: &Map) -> Vec<u32> {
+    let (components, sizes) = strongly_connected_components(map);
+    let Some(largest) = (0..sizes.len()).max_by_key(|c| sizes[*c]) else {
+        return Vec::new();
+    };
+    (0..components.len() as u32)
+        .filter(|n| components[*n as usize] == largest as u32)
+        .collect()
+}
+
+pub fn random_queries(nodes: &[u32], count: usize, random: &mut Random) -> Vec<Query> {
+    (0..count)
+        .map(|_| Query {
+            source: nodes[random.below(nodes.len())],
+            target: nodes[random.below(nodes.len())],
+            set: "random".to_owned(),
+        })
+        .collect()
+}
+
+/// Dijkstra-rank queries: for every random source, the targets settled as number 2, 4, 8, ...
+/// The rank says how far a search has to go, independent of the drive times.
+pub fn rank_queries(
+    map: &Map,
+    nodes: &[u32],
+    source_count: usize,
+    random: &mut Random,
+) -> Vec<Query> {
+    let mut queries = Vec::new();
+    let mut search = Search::new(map.edges.len());
+    for _ in 0..source_count {
+        let source = nodes[random.below(nodes.len())];
+        let order = settle_order(map, source as usize, &mut search);
+        let mut exponent = 1;
+        while 1 << exponent < order.len() {
+            queries.push(Query {
+                source,
+                target: order[1 << exponent],
+                set: format!("rank 2^{}", exponent),
+            });
+            exponent += 1;
+        }
+    }
+    queries
+}
+
+fn settle_order(map: &Map, source: usize, search: &mut Search) -> Vec<u32> {
+    let mut order = Vec::new();
+    search.run(map, &[source], |_| 0, |number, _| {
+        order.push(number as u32);
+        ControlFlow::Continue(())
+    });
+    order
+}
+
+/// A search to compare. `run` takes (source, target) and returns (drive time, visited nodes).
+pub struct Algorithm<'a> {
+    pub name: &'static str,
+    pub run: Box<dyn Fn(usize, usize) -> (u32, usize) + 'a>,
+}
+
+impl<'a> Algorithm<'a> {
+    pub fn new(name: &'static str, run: impl Fn(usize, usize) -> (u32, usize) + 'a) -> Self {
+        Self {
+            name,
+            run: Box::new(run),
+        }
+    }
+}
+
+pub struct Measurement {
+    pub milliseconds: f64,
+    pub visited: usize,
+    pub distance: u32,
+}
+
+/// Percentiles of one query set and algorithm.
+pub struct Summary {
+    pub set: String,
+    pub algorithm: &'static str,
+    /// (mean, median, 90th percentile, 99th percentile, max)
+    pub milliseconds: (f64, f64, f64, f64, f64),
+    pub visited: (f64, f64, f64, f64, f64),
+}
+
+pub struct BenchmarkResult {
+    pub queries: Vec<Query>,
+    pub algorithms: Vec<&'static str>,
+    /// One row per query with one measurement per algorithm.
+    pub measurements: Vec<Vec<Measurement>>,
+    /// (query, algorithm) of every result that differs from the first algorithm's distance.
+    pub mismatches: Vec<(usize, usize)>,
+}
+
+/// Runs every algorithm on every query and checks the distances against the first algorithm.
+pub fn run_benchmark(queries: Vec<Query>, algorithms: &[Algorithm]) -> BenchmarkResult {
+    let mut measurements = Vec::with_capacity(queries.len());
+    let mut mismatches = Vec::new();
+    for (i, query) in queries.iter().enumerate() {
+        let row: Vec<Measurement> = algorithms
+            .iter()
+            .map(|algorithm| {
+                let timer = Instant::now();
+                let (distance, visited) =
+                    (algorithm.run)(query.source as usize, query.target as usize);
+                Measurement {
+                    milliseconds: timer.elapsed().as_secs_f64() * 1000.0,
+                    visited,
+                    distance,
+                }
+            })
+            .collect();
+        for (j, measurement) in row.iter().enumerate().skip(1) {
+            if measurement.distance != row[0].distance {
+                mismatches.push((i, j));
+            }
+        }
+        measurements.push(row);
+    }
+    BenchmarkResult {
+        queries,
+        algorithms: algorithms.iter().map(|a| a.name).collect(),
+        measurements,
+        mismatches,
+    }
+}
+
+fn percentiles(mut values: Vec<f64>) -> (f64, f64, f64, f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0, 0.0, 0.0, 0.0);
+    }
+    values.sort_unstable_by(f64::total_cmp);
+    let nearest_rank = |p: f64| values[((p * values.len() as f64).ceil() as usize).max(1) - 1];
+    (
+        values.iter().sum::<f64>() / values.len() as f64,
+        nearest_rank(0.5),
+        nearest_rank(0.9),
+        nearest_rank(0.99),
+        values[values.len() - 1],
+    )
+}
+
+impl BenchmarkResult {
+    /// One summary per query set and algorithm, sets in the order they first appear.
+    pub fn summaries(&self) -> Vec<Summary> {
+        let mut sets: Vec<&str> = Vec::new();
+        for query in &self.queries {
+            if !sets.contains(&query.set.as_str()) {
+                sets.push(&query.set);
+            }
+        }
+
+        let mut summaries = Vec::new();
+        for set in sets {
+            let rows: Vec<&Vec<Measurement>> = self
+                .queries
+                .iter()
+                .zip(&self.measurements)
+                .filter(|(query, _)| query.set == set)
+                .map(|(_, row)| row)
+                .collect();
+            for (j, algorithm) in self.algorithms.iter().enumerate() {
+                summaries.push(Summary {
+                    set: set.to_owned(),
+                    algorithm,
+                    milliseconds: percentiles(rows.iter().map(|r| r[j].milliseconds).collect()),
+                    visited: percentiles(rows.iter().map(|r| r[j].visited as f64).collect()),
+                });
+            }
+        }
+        summaries
+    }
+
+    pub fn to_table(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "{:<12}{:<14}{:>10}{:>10}{:>10}{:>10}{:>12}{:>12}",
+            "Set", "Algorithm", "Mean ms", "p50 ms", "p90 ms", "p99 ms", "p50 visit", "p99 visit"
+        );
+        for summary in self.summaries() {
+            let (mean, p50, p90, p99, _) = summary.milliseconds;
+            let _ = writeln!(
+                text,
+                "{:<12}{:<14}{:>10.3}{:>10.3}{:>10.3}{:>10.3}{:>12}{:>12}",
+                summary.set,
+                summary.algorithm,
+                mean,
+                p50,
+                p90,
+                p99,
+                format_number(summary.visited.1 as isize),
+                format_number(summary.visited.3 as isize)
+            );
+        }
+        let _ = writeln!(
+            text,
+            "{} queries, {} results disagree with {}",
+            format_number(self.queries.len() as isize),
+            format_number(self.mismatches.len() as isize),
+            self.algorithms.first().unwrap_or(&"-")
+        );
+        for &(i, j) in self.mismatches.iter().take(10) {
+            let query = &self.queries[i];
+            let _ = writeln!(
+                text,
+                "  {} -> {}: {} gives {}, {} gives {}",
+                query.source,
+                query.target,
+                self.algorithms[0],
+                self.measurements[i][0].distance,
+                self.algorithms[j],
+                self.measurements[i][j].distance
+            );
+        }
+        text
+    }
+
+    /// Every measurement, one row per query and algorithm.
+    pub fn write_csv(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(
+            writer,
+            "query,set,source,target,algorithm,milliseconds,visited,distance"
+        )?;
+        for (i, (query, row)) in self.queries.iter().zip(&self.measurements).enumerate() {
+            for (algorithm, measurement) in self.algorithms.iter().zip(row) {
+                writeln!(
+                    writer,
+                    "{},{},{},{},{},{},{},{}",
+                    i,
+                    query.set,
+                    query.source,
+                    query.target,
+                    algorithm,
+                    measurement.milliseconds,
+                    measurement.visited,
+                    measurement.distance
+                )?;
+            }
+        }
+        writer.flush()
+    }
+
+    pub fn write_summary_csv(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "set,algorithm,metric,mean,p50,p90,p99,max")?;
+        for summary in self.summaries() {
+            for (metric, (mean, p50, p90, p99, max)) in [
+                ("milliseconds", summary.milliseconds),
+                ("visited", summary.visited),
+            ] {
+                writeln!(
+                    writer,
+                    "{},{},{},{},{},{},{},{}",
+                    summary.set, summary.algorithm, metric, mean, p50, p90, p99, max
+                )?;
+            }
+        }
+        writer.flush()
+    }
+}
@@ -0,0 +1,116 @@
+use crate::graph::{Graph, Reversed};
+use crate::queue::{DefaultQueue, PriorityQueue};
+use crate::{Map, SearchResult, UNREACHED};
+
+/// One side of the bidirectional search.
+struct Side {
+    distances: Vec<u32>,
+    previous: Vec<Option<usize>>,
+    priority_queue: DefaultQueue,
+    /// Key of the last node settled, no node left in the queue has a lower one.
+    last_settled: u32,
+}
+
+impl Side {
+    fn new(node_count: usize, start: usize) -> Self {
+        let mut side = Self {
+            distances: vec![UNREACHED; node_count],
+            previous: vec![None; node_count],
+            priority_queue: DefaultQueue::new(node_count),
+            last_settled: 0,
+        };
+        side.distances[start] = 0;
+        side.priority_queue.push(start, 0);
+        side
+    }
+}
+
+/// Relaxes the edges out of `number` on one side and keeps the drive time of the shortest route
+/// through a node the other side has reached in `best`, together with that node.
+fn relax(
+    graph: &impl Graph,
+    this: &mut Side,
+    that: &Side,
+    number: usize,
+    distance: u32,
+    best: &mut (u32, usize),
+) {
+    for (to, weight) in graph.neighbours(number) {
+        let alt = distance.saturating_add(weight);
+        if alt < this.distances[to] {
+            this.distances[to] = alt;
+            this.previous[to] = Some(number);
+            this.priority_queue.push(to, alt);
+        }
+        let route = alt.saturating_add(that.distances[to]);
+        if route < best.0 {
+            *best = (route, to);
+        }
+    }
+}
+
+/// Dijkstra from the source on the map and from the goal on `reverse`, the reversed map, taking
+/// turns until they meet. Every edge relaxed next to a node the other side has reached gives a
+/// route. The search stops once the last keys settled on both sides add up to at least the
+/// shortest of those routes, since no route left can be shorter. Returns None if the goal can
+/// not be reached.
+pub fn bidirectional_dijkstra(
+    map: &Map,
+    reverse: &Reversed,
+    source: usize,
+    goal: usize,
+) -> Option<SearchResult> {
+    let node_count = map.node_count();
+    let mut sides = [Side::new(node_count, source), Side::new(node_count, goal)];
+    let mut best = (if source == goal { 0 } else { UNREACHED }, source);
+    let mut visited = Vec::new();
+
+    let mut turn = 0;
+    loop {
+        let other = 1 - turn;
+        let Some((number, distance)) = sides[turn].priority_queue.pop() else {
+            break;
+        };
+        if distance.saturating_add(sides[other].last_settled) >= best.0 {
+            break;
+        }
+        sides[turn].last_settled = distance;
+        visited.push(number as u32);
+
+        let (forward, backward) = sides.split_at_mut(1);
+        let (this, that) = if turn == 0 {
+            (&mut forward[0], &backward[0])
+        } else {
+            (&mut backward[0], &forward[0])
+        };
+        if turn == 0 {
+            relax(map, this, that, number, distance, &mut best);
+        } else {
+            relax(reverse, this, that, number, distance, &mut best);
+        }
+        turn = other;
+    }
+    let (drive_time, meeting) = best;
+    if drive_time >= UNREACHED {
+        return None;
+    }
+
+    // The forward side leads from the source to the meeting node, the backward one on to the goal
+    let mut path = vec![meeting as u32];
+    let mut current = meeting;
+    while let Some(previous) = sides[0].previous[current] {
+        path.push(previous as u32);
+        current = previous;
+    }
+    path.reverse();
+    let mut current = meeting;
+    while let Some(next) = sides[1].previous[current] {
+        path.push(next as u32);
+        current = next;
+    }
+    Some(SearchResult {
+        drive_time,
+        path,
+        visited,
+    })
+}
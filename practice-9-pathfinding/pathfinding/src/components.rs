@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use crate::{EdgeTo, Map, Node};
+
+const UNVISITED: u32 = u32::MAX;
+
+/// Iterative Tarjan over `Map.edges`, so deep one-way chains can not overflow the stack.
+///
+/// Returns: (component of every node, size of every component). Edges pointing outside the
+/// node list are ignored.
+pub fn strongly_connected_components(map: &Map) -> (Vec<u32>, Vec<usize>) {
+    let length = map.nodes.len();
+    let mut index = vec![UNVISITED; length];
+    let mut low_link = vec![0; length];
+    let mut on_stack = vec![false; length];
+    let mut component = vec![UNVISITED; length];
+    let mut sizes = Vec::new();
+    let mut stack = Vec::new();
+    let mut call_stack: Vec<(usize, usize)> = Vec::new();
+    let mut counter = 0;
+
+    for root in 0..length {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        index[root] = counter;
+        low_link[root] = counter;
+        counter += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        call_stack.push((root, 0));
+
+        while let Some((node, next_edge)) = call_stack.last_mut() {
+            let node = *node;
+            if let Some(edge) = map.edges[node].get(*next_edge) {
+                *next_edge += 1;
+                let to = edge.to as usize;
+                if to >= length {
+                    continue;
+                }
+                if index[to] == UNVISITED {
+                    index[to] = counter;
+                    low_link[to] = counter;
+                    counter += 1;
+                    stack.push(to);
+                    on_stack[to] = true;
+                    call_stack.push((to, 0));
+                } else if on_stack[to] {
+                    low_link[node] = low_link[node].min(index[to]);
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                low_link[parent] = low_link[parent].min(low_link[node]);
+            }
+            if low_link[node] == index[node] {
+                let id = sizes.len() as u32;
+                let mut size = 0;
+                loop {
+                    let member = stack.pop().expect("Tarjan stack is empty");
+                    on_stack[member] = false;
+                    component[member] = id;
+                    size += 1;
+                    if member == node {
+                        break;
+                    }
+                }
+                sizes.push(size);
+            }
+        }
+    }
+    (component, sizes)
+}
+
+impl Map {
+    /// Copy of the map with only the nodes marked in `keep`, renumbered contiguously.
+    /// Edges and points of interest follow their nodes.
+    ///
+    /// Returns: (map, original id of every new node)
+    pub fn get_sub_map(&self, keep: &[bool]) -> (Map, Vec<u32>) {
+        let mut new_ids = vec![UNVISITED; self.nodes.len()];
+        let mut original_ids = Vec::new();
+        for (i, _) in keep.iter().enumerate().filter(|(_, k)| **k) {
+            new_ids[i] = original_ids.len() as u32;
+            original_ids.push(i as u32);
+        }
+
+        let mut nodes = Vec::with_capacity(original_ids.len());
+        let mut edges = Vec::with_capacity(original_ids.len());
+        for (new_id, &original) in original_ids.iter().enumerate() {
+            let node = &self.nodes[original as usize];
+            nodes.push(Node::new(new_id as u32, node.latitude, node.longitude));
+            edges.push(
+                self.edges[original as usize]
+                    .iter()
+                    .filter(|e| new_ids.get(e.to as usize).is_some_and(|&n| n != UNVISITED))
+                    .map(|e| {
+                        EdgeTo::new(
+                            new_ids[e.to as usize],
+                            e.drive_time,
+                            e.length,
+                            e.speed_limit,
+                        )
+                    })
+                    .collect::<Vec<EdgeTo>>(),
+            );
+        }
+
+        let points_of_interest: HashMap<u32, (u8, String)> = self
+            .points_of_interest
+            .iter()
+            .filter(|(node, _)| {
+                new_ids
+                    .get(**node as usize)
+                    .is_some_and(|&n| n != UNVISITED)
+            })
+            .map(|(node, poi)| (new_ids[*node as usize], poi.clone()))
+            .collect();
+
+        (
+            Map::from_nodes_edges_and_poi(nodes, edges, points_of_interest),
+            original_ids,
+        )
+    }
+
+    /// Copy of the map with only the largest strongly connected component.
+    ///
+    /// Returns: (map, original id of every new node)
+    pub fn get_largest_component_copy(&self) -> (Map, Vec<u32>) {
+        let (component, sizes) = strongly_connected_components(self);
+        let largest = sizes
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, size)| **size)
+            .map_or(UNVISITED, |(id, _)| id as u32);
+        let keep: Vec<bool> = component.iter().map(|c| *c == largest).collect();
+        self.get_sub_map(&keep)
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+use crate::directions::Route;
+use crate::graph::{Reversed, Search};
+use crate::queue::DefaultQueue;
+use crate::Map;
+
+/// A stop at a point of interest on the way, found by `detours`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detour {
+    pub point_of_interest: u32,
+    /// Centiseconds on top of the drive time of the route, 0 if the detour is faster.
+    pub added_time: u32,
+    /// Indices into the route of the nodes it is left at and rejoined at.
+    pub leave: usize,
+    pub rejoin: usize,
+    /// The whole route with the detour, from the source to the goal.
+    pub path: Vec<u32>,
+    pub drive_time: u32,
+}
+
+/// The `count` points of interest with all the bits of `category` that add the least drive time
+/// to `path`, cheapest first. `reverse` is the reversed map.
+///
+/// The search forward starts from every node of the path at once, each at the drive time from
+/// the source to it, so it finds the drive time from the source to each point over the best node
+/// to leave the route at. The search on the reversed map starts each node at the drive time left
+/// from it to the goal, and finds the best node to rejoin at. Their sum is the drive time of the
+/// best detour, which may rejoin the route behind the node it was left at. Only points that add
+/// at most `max_added` centiseconds are searched for, which keeps the searches near the route.
+/// Returns None if the path does not follow the edges of the map.
+pub fn detours(
+    map: &Map,
+    reverse: &Reversed,
+    path: &[u32],
+    category: u8,
+    max_added: u32,
+    count: usize,
+) -> Option<Vec<Detour>> {
+    let route = Route::from_node_path(map, path)?;
+    // Drive time from the source to every node of the path
+    let mut arrivals = vec![0];
+    for edge in &route.edges {
+        arrivals.push(arrivals.last().unwrap() + edge.edge.drive_time);
+    }
+    let total = *arrivals.last().unwrap();
+    let limit = total.saturating_add(max_added);
+    // A node on the path twice is best left at its first visit and rejoined at its last
+    let mut first = HashMap::new();
+    let mut last = HashMap::new();
+    for (i, node) in path.iter().enumerate() {
+        first.entry(*node as usize).or_insert(i);
+        last.insert(*node as usize, i);
+    }
+    let stop_past_limit = |_, cost| {
+        if cost > limit {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    };
+
+    let mut forward = Search::<DefaultQueue>::new(map.edges.len());
+    let starts = path.iter().zip(&arrivals).map(|(n, a)| (*n as usize, *a));
+    forward.run_from(map, starts, |_| 0, stop_past_limit);
+    let mut backward = Search::<DefaultQueue>::new(map.edges.len());
+    let starts = path
+        .iter()
+        .zip(&arrivals)
+        .map(|(n, a)| (*n as usize, total - a));
+    backward.run_from(reverse, starts, |_| 0, stop_past_limit);
+
+    let mut detours: Vec<Detour> = map
+        .points_of_interest
+        .iter()
+        .filter(|(_, (c, _))| c & category == category)
+        .filter_map(|(poi, _)| {
+            let poi = *poi as usize;
+            // Nodes left unsettled when a search stopped cost more than the limit
+            let drive_time = forward
+                .distance(poi)?
+                .checked_add(backward.distance(poi)?)?;
+            if drive_time > limit {
+                return None;
+            }
+            let out = forward.path_to(poi)?;
+            let mut back = backward.path_to(poi)?;
+            back.reverse();
+            let leave = first[&(out[0] as usize)];
+            let rejoin = last[&(*back.last()? as usize)];
+
+            let mut detour_path = path[..=leave].to_vec();
+            detour_path.extend(&out[1..]);
+            detour_path.extend(&back[1..]);
+            detour_path.extend(&path[rejoin + 1..]);
+            Some(Detour {
+                point_of_interest: poi as u32,
+                added_time: drive_time.saturating_sub(total),
+                leave,
+                rejoin,
+                path: detour_path,
+                drive_time,
+            })
+        })
+        .collect();
+    detours.sort_by_key(|d| (d.added_time, d.point_of_interest));
+    detours.truncate(count);
+    Some(detours)
+}
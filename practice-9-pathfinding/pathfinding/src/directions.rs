@@ -0,0 +1,231 @@
+use crate::{EdgeTo, Map};
+
+/// Turns within this many degrees of the current bearing count as going straight on.
+const STRAIGHT: f64 = 20.0;
+const KEEP: f64 = 45.0;
+const TURN: f64 = 135.0;
+const SHARP: f64 = 170.0;
+
+/// One edge of a route, with the node it leaves from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteEdge {
+    pub from: u32,
+    pub edge: EdgeTo,
+}
+
+/// Path as the edges that were driven, so parallel edges between two nodes stay apart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub source: u32,
+    pub edges: Vec<RouteEdge>,
+}
+
+impl Route {
+    /// Picks the edge the searches relax for every step of `path`, which starts at the source.
+    /// They only replace a distance with a strictly lower one, so among parallel edges that is
+    /// the first one with the lowest drive time. Returns None if a step has no edge.
+    pub fn from_node_path(map: &Map, path: &[u32]) -> Option<Self> {
+        let mut edges = Vec::with_capacity(path.len().saturating_sub(1));
+        for pair in path.windows(2) {
+            let edge = map.edges[pair[0] as usize]
+                .iter()
+                .filter(|e| e.to == pair[1])
+                .min_by_key(|e| e.drive_time)?;
+            edges.push(RouteEdge {
+                from: pair[0],
+                edge: edge.clone(),
+            });
+        }
+        Some(Self {
+            source: *path.first()?,
+            edges,
+        })
+    }
+
+    pub fn nodes(&self) -> Vec<u32> {
+        let mut nodes = vec![self.source];
+        nodes.extend(self.edges.iter().map(|e| e.edge.to));
+        nodes
+    }
+
+    pub fn drive_time(&self) -> u32 {
+        self.edges.iter().map(|e| e.edge.drive_time).sum()
+    }
+
+    /// Length in meters.
+    pub fn length(&self) -> u32 {
+        self.edges.iter().map(|e| e.edge.length).sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Maneuver {
+    Depart,
+    ContinueStraight,
+    KeepLeft,
+    KeepRight,
+    TurnLeft,
+    TurnRight,
+    SharpLeft,
+    SharpRight,
+    UTurn,
+    Arrive,
+}
+
+impl Maneuver {
+    /// `turn` is the change in bearing in degrees, positive to the right.
+    fn from_turn(turn: f64) -> Self {
+        match (turn.abs(), turn > 0.0) {
+            (a, _) if a <= STRAIGHT => Self::ContinueStraight,
+            (a, true) if a <= KEEP => Self::KeepRight,
+            (a, false) if a <= KEEP => Self::KeepLeft,
+            (a, true) if a <= TURN => Self::TurnRight,
+            (a, false) if a <= TURN => Self::TurnLeft,
+            (a, true) if a <= SHARP => Self::SharpRight,
+            (a, false) if a <= SHARP => Self::SharpLeft,
+            _ => Self::UTurn,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Depart => "Head",
+            Self::ContinueStraight => "Continue straight",
+            Self::KeepLeft => "Keep left",
+            Self::KeepRight => "Keep right",
+            Self::TurnLeft => "Turn left",
+            Self::TurnRight => "Turn right",
+            Self::SharpLeft => "Turn sharp left",
+            Self::SharpRight => "Turn sharp right",
+            Self::UTurn => "Make a U-turn",
+            Self::Arrive => "Arrive at the destination",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub maneuver: Maneuver,
+    pub node: u32,
+    /// Bearing in degrees from north when leaving the node.
+    pub bearing: f64,
+    /// Meters and centiseconds until the next instruction.
+    pub length: u32,
+    pub drive_time: u32,
+    /// Speed limits in the order they are met until the next instruction, as a hint of the
+    /// road class.
+    pub speed_limits: Vec<u16>,
+}
+
+impl Instruction {
+    pub fn to_text(&self) -> String {
+        let action = match self.maneuver {
+            Maneuver::Arrive => return self.maneuver.name().to_owned(),
+            Maneuver::Depart => format!("Head {}", compass_direction(self.bearing)),
+            maneuver => maneuver.name().to_owned(),
+        };
+        let length = if self.length < 1000 {
+            format!("{} m", (self.length + 5) / 10 * 10)
+        } else {
+            format!("{:.1} km", self.length as f64 / 1000.0)
+        };
+        let limits: Vec<String> = self.speed_limits.iter().map(u16::to_string).collect();
+        format!(
+            "{}, then drive {} ({} km/h)",
+            action,
+            length,
+            limits.join(" -> ")
+        )
+    }
+}
+
+/// Initial great-circle bearing from one node to another, in degrees from north.
+fn bearing(map: &Map, from: u32, to: u32) -> Option<f64> {
+    let (lat1, lon1) = map.get_coordinates_from_node(from as usize);
+    let (lat2, lon2) = map.get_coordinates_from_node(to as usize);
+    if (lat1, lon1) == (lat2, lon2) {
+        return None;
+    }
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let delta = (lon2 - lon1).to_radians();
+    let y = delta.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * delta.cos();
+    Some(y.atan2(x).to_degrees().rem_euclid(360.0))
+}
+
+fn compass_direction(bearing: f64) -> &'static str {
+    const DIRECTIONS: [&str; 8] = [
+        "north",
+        "northeast",
+        "east",
+        "southeast",
+        "south",
+        "southwest",
+        "west",
+        "northwest",
+    ];
+    DIRECTIONS[((bearing + 22.5) / 45.0) as usize % 8]
+}
+
+/// Directions for the route. Turns are only announced where the route could have gone another
+/// way, so bends in a road without junctions stay quiet. A change of speed limit at a junction
+/// where the route goes straight on is announced as "continue straight".
+pub fn turn_by_turn(map: &Map, route: &Route) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut current_bearing = 0.0;
+    for (i, step) in route.edges.iter().enumerate() {
+        let new_bearing = bearing(map, step.from, step.edge.to).unwrap_or(current_bearing);
+        let maneuver = if i == 0 {
+            Some(Maneuver::Depart)
+        } else {
+            let turn = (new_bearing - current_bearing + 540.0).rem_euclid(360.0) - 180.0;
+            let came_from = route.edges[i - 1].from;
+            let mut options: Vec<u32> = map.edges[step.from as usize]
+                .iter()
+                .map(|e| e.to)
+                .filter(|to| *to != came_from)
+                .collect();
+            options.sort_unstable();
+            options.dedup();
+            let previous_limit = route.edges[i - 1].edge.speed_limit;
+            match Maneuver::from_turn(turn) {
+                Maneuver::UTurn => Some(Maneuver::UTurn),
+                _ if options.len() < 2 => None,
+                Maneuver::ContinueStraight if previous_limit == step.edge.speed_limit => None,
+                maneuver => Some(maneuver),
+            }
+        };
+        current_bearing = new_bearing;
+
+        if let Some(maneuver) = maneuver {
+            instructions.push(Instruction {
+                maneuver,
+                node: step.from,
+                bearing: new_bearing,
+                length: 0,
+                drive_time: 0,
+                speed_limits: Vec::new(),
+            });
+        }
+        let instruction = instructions
+            .last_mut()
+            .expect("The route starts with Depart");
+        instruction.length += step.edge.length;
+        instruction.drive_time += step.edge.drive_time;
+        if instruction.speed_limits.last() != Some(&step.edge.speed_limit) {
+            instruction.speed_limits.push(step.edge.speed_limit);
+        }
+    }
+
+    if let Some(last) = route.edges.last() {
+        instructions.push(Instruction {
+            maneuver: Maneuver::Arrive,
+            node: last.edge.to,
+            bearing: current_bearing,
+            length: 0,
+            drive_time: 0,
+            speed_limits: Vec::new(),
+        });
+    }
+    instructions
+}
@@ -0,0 +1,321 @@
+const SECONDS_PER_DAY: i64 = 86_400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    Norwegian,
+}
+
+impl Language {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "en" | "english" => Some(Self::English),
+            "nb" | "no" | "norwegian" => Some(Self::Norwegian),
+            _ => None,
+        }
+    }
+
+    /// (singular, plural) of "day", "hour", "minute" and "second", then the word for "and".
+    fn words(&self) -> ([(&'static str, &'static str); 4], &'static str) {
+        match self {
+            Self::English => (
+                [
+                    ("day", "days"),
+                    ("hour", "hours"),
+                    ("minute", "minutes"),
+                    ("second", "seconds"),
+                ],
+                "and",
+            ),
+            Self::Norwegian => (
+                [
+                    ("dag", "dager"),
+                    ("time", "timer"),
+                    ("minutt", "minutter"),
+                    ("sekund", "sekunder"),
+                ],
+                "og",
+            ),
+        }
+    }
+}
+
+/// Drive time in centiseconds, the unit of the edge file. Formats round down to whole seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct TravelDuration {
+    centi_seconds: u64,
+}
+
+impl TravelDuration {
+    pub fn from_centi_seconds(centi_seconds: u64) -> Self {
+        Self { centi_seconds }
+    }
+
+    pub fn from_seconds(seconds: u64) -> Self {
+        Self::from_centi_seconds(seconds * 100)
+    }
+
+    pub fn seconds(self) -> u64 {
+        self.centi_seconds / 100
+    }
+
+    /// Whole seconds, rounded up so a schedule never leaves too little time.
+    fn seconds_rounded_up(self) -> i64 {
+        self.centi_seconds.div_ceil(100) as i64
+    }
+
+    /// (hours, minutes, seconds) with the hours not wrapped into days.
+    fn hours_minutes_seconds(self) -> (u64, u64, u64) {
+        let seconds = self.seconds();
+        (seconds / 3600, seconds / 60 % 60, seconds % 60)
+    }
+
+    /// ISO 8601 duration like "PT2H5M3S". Days are left as hours since a day is not always 24
+    /// hours long in local time.
+    pub fn to_iso8601(self) -> String {
+        let (hours, minutes, seconds) = self.hours_minutes_seconds();
+        let mut text = "PT".to_owned();
+        if hours > 0 {
+            text += &format!("{}H", hours);
+        }
+        if minutes > 0 {
+            text += &format!("{}M", minutes);
+        }
+        if seconds > 0 || (hours == 0 && minutes == 0) {
+            text += &format!("{}S", seconds);
+        }
+        text
+    }
+
+    /// "HH:MM:SS", with more than two digits for hours if the trip takes over 99 hours.
+    pub fn to_clock(self) -> String {
+        let (hours, minutes, seconds) = self.hours_minutes_seconds();
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    }
+
+    /// Prose like "2 hours, 5 minutes and 3 seconds." leaving out the larger units that are 0.
+    pub fn to_text(self, language: Language) -> String {
+        let ([day, hour, minute, second], and) = language.words();
+        let seconds = self.seconds();
+        let word = |value: u64, (singular, plural): (&str, &str)| {
+            format!("{} {}", value, if value != 1 { plural } else { singular })
+        };
+
+        let mut text = format!("{}.", word(seconds % 60, second));
+        if seconds >= 60 {
+            text = format!("{} {} {}", word(seconds / 60 % 60, minute), and, text);
+        }
+        if seconds >= 3600 {
+            text = format!("{}, {}", word(seconds / 3600 % 24, hour), text);
+        }
+        if seconds >= SECONDS_PER_DAY as u64 {
+            text = format!("{}, {}", word(seconds / SECONDS_PER_DAY as u64, day), text);
+        }
+        text
+    }
+
+    /// Reads an ISO 8601 duration with hours, minutes and seconds ("PT2H5M3S", "PT90M") or
+    /// a clock duration ("02:05:03", "2:05").
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        if let Some(rest) = input.strip_prefix("PT") {
+            let mut seconds = 0.0;
+            let mut number = String::new();
+            for character in rest.chars() {
+                let factor = match character {
+                    'H' => 3600.0,
+                    'M' => 60.0,
+                    'S' => 1.0,
+                    _ => {
+                        number.push(character);
+                        continue;
+                    }
+                };
+                seconds += number.parse::<f64>().ok()? * factor;
+                number.clear();
+            }
+            if !number.is_empty() || rest.is_empty() {
+                return None;
+            }
+            return Some(Self::from_centi_seconds((seconds * 100.0).round() as u64));
+        }
+
+        let parts: Vec<u64> = input
+            .split(':')
+            .map(|p| p.parse().ok())
+            .collect::<Option<Vec<u64>>>()?;
+        match parts[..] {
+            [hours, minutes] if minutes < 60 => {
+                Some(Self::from_seconds(hours * 3600 + minutes * 60))
+            }
+            [hours, minutes, seconds] if minutes < 60 && seconds < 60 => {
+                Some(Self::from_seconds(hours * 3600 + minutes * 60 + seconds))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar, from Howard Hinnant's
+/// `days_from_civil`.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// (year, month, day) of a day count since 1970-01-01, the inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Local date and time with the UTC offset it was given in, if any.
+///
+/// Arithmetic is on the wall clock and keeps the offset, so a trip across a change to or from
+/// daylight saving time is an hour off.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp {
+    /// Seconds since 1970-01-01T00:00:00 on the local wall clock.
+    seconds: i64,
+    /// As written, "Z" or like "+02:00", empty for local time.
+    offset: String,
+}
+
+impl Timestamp {
+    /// Reads "2024-05-17T08:30", with optional seconds, a space instead of 'T', and an
+    /// optional "Z" or "+HH:MM" offset.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let (date, time) = input.split_once(['T', ' '])?;
+        let date: Vec<&str> = date.split('-').collect();
+        let [year, month, day] = date[..] else {
+            return None;
+        };
+        let (year, month, day): (i64, u32, u32) =
+            (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return None;
+        }
+
+        let offset_start = time.find(['Z', '+', '-']).unwrap_or(time.len());
+        let (time, offset) = time.split_at(offset_start);
+        if !(offset.is_empty() || offset == "Z" || Self::valid_offset(offset)) {
+            return None;
+        }
+        let clock = TravelDuration::parse(time)?.seconds() as i64;
+        if time.split(':').count() < 2 || clock >= SECONDS_PER_DAY {
+            return None;
+        }
+        Some(Self {
+            seconds: days_from_civil(year, month, day) * SECONDS_PER_DAY + clock,
+            offset: offset.to_owned(),
+        })
+    }
+
+    fn valid_offset(offset: &str) -> bool {
+        let Some((hours, minutes)) = offset[1..].split_once(':') else {
+            return false;
+        };
+        hours.len() == 2
+            && minutes.len() == 2
+            && hours.parse::<u32>().is_ok_and(|h| h <= 14)
+            && minutes.parse::<u32>().is_ok_and(|m| m < 60)
+    }
+
+    /// Arrival after driving for `duration`, rounded up to the next second.
+    pub fn add(&self, duration: TravelDuration) -> Self {
+        Self {
+            seconds: self.seconds + duration.seconds_rounded_up(),
+            offset: self.offset.clone(),
+        }
+    }
+
+    /// Latest departure to arrive at this time after driving for `duration`.
+    pub fn subtract(&self, duration: TravelDuration) -> Self {
+        Self {
+            seconds: self.seconds - duration.seconds_rounded_up(),
+            offset: self.offset.clone(),
+        }
+    }
+
+    /// "2024-05-17T08:30:00" followed by the offset the timestamp was given with.
+    pub fn to_iso8601(&self) -> String {
+        let (year, month, day) = civil_from_days(self.seconds.div_euclid(SECONDS_PER_DAY));
+        let clock = TravelDuration::from_seconds(self.seconds.rem_euclid(SECONDS_PER_DAY) as u64);
+        format!(
+            "{:04}-{:02}-{:02}T{}{}",
+            year,
+            month,
+            day,
+            clock.to_clock(),
+            self.offset
+        )
+    }
+}
+
+/// When to leave or when to be there, for reporting the other end of a route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    DepartAt(Timestamp),
+    ArriveBy(Timestamp),
+}
+
+impl Schedule {
+    /// (departure, arrival) for a route taking `duration`.
+    pub fn times(&self, duration: TravelDuration) -> (Timestamp, Timestamp) {
+        match self {
+            Self::DepartAt(departure) => (departure.clone(), departure.add(duration)),
+            Self::ArriveBy(arrival) => (arrival.subtract(duration), arrival.clone()),
+        }
+    }
+
+    pub fn describe(&self, duration: TravelDuration, language: Language) -> String {
+        let (departure, arrival) = self.times(duration);
+        let (departure, arrival) = (departure.to_iso8601(), arrival.to_iso8601());
+        let iso = duration.to_iso8601();
+        match (self, language) {
+            (Self::DepartAt(_), Language::English) => {
+                format!("Departing {}, arriving {} ({})", departure, arrival, iso)
+            }
+            (Self::DepartAt(_), Language::Norwegian) => {
+                format!("Avreise {}, ankomst {} ({})", departure, arrival, iso)
+            }
+            (Self::ArriveBy(_), Language::English) => {
+                format!("Leave by {} to arrive at {} ({})", departure, arrival, iso)
+            }
+            (Self::ArriveBy(_), Language::Norwegian) => {
+                format!(
+                    "Reis senest {} for å være fremme {} ({})",
+                    departure, arrival, iso
+                )
+            }
+        }
+    }
+}
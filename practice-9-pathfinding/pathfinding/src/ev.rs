@@ -0,0 +1,270 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::Map;
+
+const CHARGING_STATION: u8 = 4;
+
+/// Time spent at a charger: `fixed` plus `per_km` for every km charged, both in centiseconds.
+/// The car is always charged to full range.
+#[derive(Debug, Clone, Copy)]
+pub struct ChargingModel {
+    pub fixed: u32,
+    pub per_km: u32,
+}
+
+impl ChargingModel {
+    pub fn new(fixed: u32, per_km: u32) -> Self {
+        Self { fixed, per_km }
+    }
+
+    fn charging_time(&self, charged_meters: u32) -> usize {
+        self.fixed as usize + (self.per_km as u64 * charged_meters as u64 / 1000) as usize
+    }
+}
+
+pub struct EvRoute {
+    /// Driving plus charging time in centiseconds.
+    pub travel_time: usize,
+    pub charging_time: usize,
+    pub path: Vec<u32>,
+    pub chargers: Vec<u32>,
+    /// Remaining range at the goal in meters.
+    pub arrival_charge: u32,
+}
+
+/// Label of a stop in the search over chargers. A stop is a node plus whether the car
+/// charges there, so a source that is also a charger can be left with or without charging.
+/// `leg` is the label of the leg search from the parent stop that reached it.
+#[derive(Clone, Copy)]
+struct StopLabel {
+    departure: usize,
+    arrival: usize,
+    arrival_charge: u32,
+    parent: Option<((u32, bool), usize)>,
+}
+
+/// A way to a node within range: drive time, driven meters and the label it was extended from.
+#[derive(Clone, Copy)]
+struct LegLabel {
+    node: usize,
+    time: usize,
+    meters: u32,
+    parent: Option<usize>,
+    dominated: bool,
+}
+
+/// Every way to drive from a stop within `budget` meters that is not both slower and longer
+/// than another way to the same node.
+struct LegSearch {
+    labels: Vec<LegLabel>,
+    /// Labels that are not dominated, per reached node.
+    pareto: HashMap<usize, Vec<usize>>,
+}
+
+impl LegSearch {
+    /// Dijkstra on drive time that keeps a set of (drive time, meters) labels per node instead
+    /// of one distance, so a slower but shorter path is kept for when the fastest one runs out
+    /// of charge. Drive time and length go mostly together on roads, so the sets stay small.
+    fn new(map: &Map, source: usize, budget: u32) -> Self {
+        let mut search = Self {
+            labels: Vec::new(),
+            pareto: HashMap::new(),
+        };
+        let mut priority_queue = BinaryHeap::new();
+        search.add(source, 0, 0, None);
+        priority_queue.push(Reverse((0, 0)));
+
+        while let Some(Reverse((_, index))) = priority_queue.pop() {
+            let label = search.labels[index];
+            if label.dominated {
+                continue;
+            }
+            for neighbour in &map.edges[label.node] {
+                let meters = label.meters + neighbour.length;
+                if meters > budget {
+                    continue;
+                }
+                let time = label.time + neighbour.drive_time as usize;
+                let to = neighbour.to as usize;
+                if let Some(added) = search.add(to, time, meters, Some(index)) {
+                    priority_queue.push(Reverse((time, added)));
+                }
+            }
+        }
+        search
+    }
+
+    /// Adds the label unless one at the node is at least as good in both, and marks the labels
+    /// it beats as dominated. Returns the index of the new label.
+    fn add(
+        &mut self,
+        node: usize,
+        time: usize,
+        meters: u32,
+        parent: Option<usize>,
+    ) -> Option<usize> {
+        let labels = &mut self.labels;
+        let at_node = self.pareto.entry(node).or_default();
+        if at_node
+            .iter()
+            .any(|&i| labels[i].time <= time && labels[i].meters <= meters)
+        {
+            return None;
+        }
+        at_node.retain(|&i| {
+            let beaten = time <= labels[i].time && meters <= labels[i].meters;
+            labels[i].dominated |= beaten;
+            !beaten
+        });
+        at_node.push(labels.len());
+        labels.push(LegLabel {
+            node,
+            time,
+            meters,
+            parent,
+            dominated: false,
+        });
+        Some(labels.len() - 1)
+    }
+
+    fn path(&self, label: usize) -> Vec<u32> {
+        let mut path = vec![self.labels[label].node as u32];
+        let mut current = label;
+        while let Some(p) = self.labels[current].parent {
+            path.push(self.labels[p].node as u32);
+            current = p;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Fastest route from source to goal for an electric car with `range` meters on a full battery,
+/// starting with `start_charge` meters, stopping at charging stations whenever needed.
+///
+/// Searches over stops (source, chargers, goal) in order of departure time, running a
+/// `LegSearch` from every stop that is settled. The car leaves every charger full, so only the
+/// earliest departure from it matters. Returns None if no sequence of chargers connects source
+/// and goal.
+pub fn ev_route(
+    map: &Map,
+    source: usize,
+    goal: usize,
+    range: u32,
+    start_charge: u32,
+    charging: &ChargingModel,
+) -> Option<EvRoute> {
+    let start = (source as u32, false);
+    let mut labels: HashMap<(u32, bool), StopLabel> = HashMap::new();
+    let mut settled: HashSet<(u32, bool)> = HashSet::new();
+    let mut stops: BinaryHeap<Reverse<(usize, (u32, bool))>> = BinaryHeap::new();
+    labels.insert(
+        start,
+        StopLabel {
+            departure: 0,
+            arrival: 0,
+            arrival_charge: start_charge,
+            parent: None,
+        },
+    );
+    stops.push(Reverse((0, start)));
+
+    let mut found = false;
+    while let Some(Reverse((departure, stop))) = stops.pop() {
+        if !settled.insert(stop) {
+            continue;
+        }
+        if stop.0 as usize == goal {
+            found = true;
+            break;
+        }
+        let budget = if stop.1 { range } else { start_charge };
+        let legs = LegSearch::new(map, stop.0 as usize, budget);
+        for (&node, at_node) in &legs.pareto {
+            let is_goal = node == goal;
+            let is_charger = map
+                .points_of_interest
+                .get(&(node as u32))
+                .is_some_and(|poi| poi.0 & CHARGING_STATION == CHARGING_STATION);
+            if !is_goal && !is_charger {
+                continue;
+            }
+            // Earliest departure from the next stop over the ways there, and the way used
+            let mut candidates: Vec<((u32, bool), usize, usize)> = Vec::new();
+            for &index in at_node {
+                let leg = legs.labels[index];
+                let arrival = departure + leg.time;
+                let next = if is_goal {
+                    ((node as u32, false), arrival)
+                } else {
+                    let charged = range.saturating_sub(budget - leg.meters);
+                    (
+                        (node as u32, true),
+                        arrival + charging.charging_time(charged),
+                    )
+                };
+                match candidates.iter_mut().find(|c| c.0 == next.0) {
+                    Some(best) if best.1 <= next.1 => {}
+                    Some(best) => *best = (next.0, next.1, index),
+                    None => candidates.push((next.0, next.1, index)),
+                }
+            }
+            for (next, next_departure, index) in candidates {
+                if settled.contains(&next)
+                    || labels
+                        .get(&next)
+                        .is_some_and(|l| l.departure <= next_departure)
+                {
+                    continue;
+                }
+                let leg = legs.labels[index];
+                labels.insert(
+                    next,
+                    StopLabel {
+                        departure: next_departure,
+                        arrival: departure + leg.time,
+                        arrival_charge: budget - leg.meters,
+                        parent: Some((stop, index)),
+                    },
+                );
+                stops.push(Reverse((next_departure, next)));
+            }
+        }
+    }
+    if !found {
+        return None;
+    }
+
+    // Walk back over the stops, then redo each leg search to get the nodes of its way
+    let mut chain = vec![((goal as u32, false), None)];
+    while let Some((parent, leg)) = labels[&chain.last().unwrap().0].parent {
+        chain.last_mut().unwrap().1 = Some(leg);
+        chain.push((parent, None));
+    }
+    chain.reverse();
+
+    let mut path = vec![source as u32];
+    let mut chargers = Vec::new();
+    let mut charging_time = 0;
+    for leg in chain.windows(2) {
+        let (from, (to, index)) = (leg[0].0, leg[1]);
+        let budget = if from.1 { range } else { start_charge };
+        let legs = LegSearch::new(map, from.0 as usize, budget);
+        path.extend(&legs.path(index.unwrap())[1..]);
+        if to.1 {
+            let label = labels[&to];
+            chargers.push(to.0);
+            charging_time += label.departure - label.arrival;
+        }
+    }
+
+    let goal_label = labels[&(goal as u32, false)];
+    Some(EvRoute {
+        travel_time: goal_label.arrival,
+        charging_time,
+        path,
+        chargers,
+        arrival_charge: goal_label.arrival_charge,
+    })
+}
@@ -0,0 +1,64 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::geo::Polygon;
+use crate::Map;
+
+impl Map {
+    /// Copy of the map with only the nodes inside the region, see `get_sub_map`.
+    pub fn get_region_copy(&self, region: &Polygon) -> (Map, Vec<u32>) {
+        let keep: Vec<bool> = self
+            .nodes
+            .iter()
+            .map(|n| region.contains(n.latitude, n.longitude))
+            .collect();
+        self.get_sub_map(&keep)
+    }
+}
+
+/// Writes the map in the same format `get_map_from_paths` reads. Node ids are expected to be
+/// contiguous, as they are after `get_sub_map`.
+pub fn write_map_to_paths(
+    map: &Map,
+    node_path: &str,
+    edge_path: &str,
+    poi_path: &str,
+) -> io::Result<()> {
+    let mut node_writer = BufWriter::new(File::create(node_path)?);
+    writeln!(node_writer, "{}", map.nodes.len())?;
+    for node in &map.nodes {
+        writeln!(
+            node_writer,
+            "{}\t{}\t{}",
+            node.id, node.latitude, node.longitude
+        )?;
+    }
+    node_writer.flush()?;
+
+    let mut edge_writer = BufWriter::new(File::create(edge_path)?);
+    writeln!(
+        edge_writer,
+        "{}",
+        map.edges.iter().map(Vec::len).sum::<usize>()
+    )?;
+    for (from, edges) in map.edges.iter().enumerate() {
+        for edge in edges {
+            writeln!(
+                edge_writer,
+                "{}\t{}\t{}\t{}\t{}",
+                from, edge.to, edge.drive_time, edge.length, edge.speed_limit
+            )?;
+        }
+    }
+    edge_writer.flush()?;
+
+    let mut points_of_interest: Vec<(&u32, &(u8, String))> =
+        map.points_of_interest.iter().collect();
+    points_of_interest.sort_unstable_by_key(|(node, _)| **node);
+    let mut poi_writer = BufWriter::new(File::create(poi_path)?);
+    writeln!(poi_writer, "{}", points_of_interest.len())?;
+    for (node, (category, name)) in points_of_interest {
+        writeln!(poi_writer, "{}\t{}\t\"{}\"", node, category, name)?;
+    }
+    poi_writer.flush()
+}
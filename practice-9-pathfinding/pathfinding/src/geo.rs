@@ -0,0 +1,162 @@
+use crate::Map;
+
+/// Closed polygon in (latitude, longitude) used for avoid-zones and region cut-outs.
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    points: Vec<(f64, f64)>,
+    min_latitude: f64,
+    max_latitude: f64,
+    min_longitude: f64,
+    max_longitude: f64,
+}
+
+impl Polygon {
+    pub fn new(points: Vec<(f64, f64)>) -> Self {
+        assert!(points.len() >= 3, "A polygon needs at least three points");
+        let mut polygon = Self {
+            points,
+            min_latitude: f64::MAX,
+            max_latitude: f64::MIN,
+            min_longitude: f64::MAX,
+            max_longitude: f64::MIN,
+        };
+        for &(latitude, longitude) in &polygon.points {
+            polygon.min_latitude = polygon.min_latitude.min(latitude);
+            polygon.max_latitude = polygon.max_latitude.max(latitude);
+            polygon.min_longitude = polygon.min_longitude.min(longitude);
+            polygon.max_longitude = polygon.max_longitude.max(longitude);
+        }
+        polygon
+    }
+
+    pub fn from_bounding_box(
+        min_latitude: f64,
+        min_longitude: f64,
+        max_latitude: f64,
+        max_longitude: f64,
+    ) -> Self {
+        Self::new(vec![
+            (min_latitude, min_longitude),
+            (min_latitude, max_longitude),
+            (max_latitude, max_longitude),
+            (max_latitude, min_longitude),
+        ])
+    }
+
+    /// Parses "lat,lon;lat,lon;..." or, with exactly four numbers, "lat1,lon1,lat2,lon2" as a box.
+    pub fn parse(input: &str) -> Option<Self> {
+        let numbers: Vec<f64> = input
+            .split([',', ';'])
+            .map(|n| n.trim().parse().ok())
+            .collect::<Option<Vec<f64>>>()?;
+        if numbers.len() == 4 && !input.contains(';') {
+            return Some(Self::from_bounding_box(
+                numbers[0].min(numbers[2]),
+                numbers[1].min(numbers[3]),
+                numbers[0].max(numbers[2]),
+                numbers[1].max(numbers[3]),
+            ));
+        }
+        if numbers.len() < 6 || !numbers.len().is_multiple_of(2) {
+            return None;
+        }
+        Some(Self::new(numbers.chunks(2).map(|p| (p[0], p[1])).collect()))
+    }
+
+    /// Ray casting point-in-polygon test, with a bounding box check first.
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        if latitude < self.min_latitude
+            || latitude > self.max_latitude
+            || longitude < self.min_longitude
+            || longitude > self.max_longitude
+        {
+            return false;
+        }
+
+        let mut inside = false;
+        let mut j = self.points.len() - 1;
+        for i in 0..self.points.len() {
+            let (lat_i, lon_i) = self.points[i];
+            let (lat_j, lon_j) = self.points[j];
+            if (lat_i > latitude) != (lat_j > latitude)
+                && longitude < (lon_j - lon_i) * (latitude - lat_i) / (lat_j - lat_i) + lon_i
+            {
+                inside = !inside;
+            }
+            j = i;
+        }
+        inside
+    }
+}
+
+/// Latitude/longitude rectangle in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_latitude: f64,
+    pub min_longitude: f64,
+    pub max_latitude: f64,
+    pub max_longitude: f64,
+}
+
+impl BoundingBox {
+    /// Smallest box around the nodes, grown by `margin` of its size on every side.
+    pub fn around(map: &Map, nodes: &[u32], margin: f64) -> Option<Self> {
+        let mut nodes = nodes
+            .iter()
+            .map(|n| map.get_coordinates_from_node(*n as usize));
+        let (latitude, longitude) = nodes.next()?;
+        let mut bounds = Self {
+            min_latitude: latitude,
+            min_longitude: longitude,
+            max_latitude: latitude,
+            max_longitude: longitude,
+        };
+        for (latitude, longitude) in nodes {
+            bounds.min_latitude = bounds.min_latitude.min(latitude);
+            bounds.min_longitude = bounds.min_longitude.min(longitude);
+            bounds.max_latitude = bounds.max_latitude.max(latitude);
+            bounds.max_longitude = bounds.max_longitude.max(longitude);
+        }
+        // Keep a single node or a straight line from giving an empty box
+        let latitude_margin = ((bounds.max_latitude - bounds.min_latitude) * margin).max(0.001);
+        let longitude_margin = ((bounds.max_longitude - bounds.min_longitude) * margin).max(0.001);
+        bounds.min_latitude -= latitude_margin;
+        bounds.max_latitude += latitude_margin;
+        bounds.min_longitude -= longitude_margin;
+        bounds.max_longitude += longitude_margin;
+        Some(bounds)
+    }
+
+    /// Parses "lat1,lon1,lat2,lon2" with the corners in any order.
+    pub fn parse(input: &str) -> Option<Self> {
+        let numbers: Vec<f64> = input
+            .split(',')
+            .map(|n| n.trim().parse().ok())
+            .collect::<Option<Vec<f64>>>()?;
+        if numbers.len() != 4 {
+            return None;
+        }
+        Some(Self {
+            min_latitude: numbers[0].min(numbers[2]),
+            min_longitude: numbers[1].min(numbers[3]),
+            max_latitude: numbers[0].max(numbers[2]),
+            max_longitude: numbers[1].max(numbers[3]),
+        })
+    }
+
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        (self.min_latitude..=self.max_latitude).contains(&latitude)
+            && (self.min_longitude..=self.max_longitude).contains(&longitude)
+    }
+}
+
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Great-circle distance in meters.
+pub fn haversine_distance(latitude1: f64, longitude1: f64, latitude2: f64, longitude2: f64) -> f64 {
+    let (lat1, lat2) = (latitude1.to_radians(), latitude2.to_radians());
+    let half_lat = (lat2 - lat1) / 2.0;
+    let half_lon = (longitude2 - longitude1).to_radians() / 2.0;
+    let a = half_lat.sin().powi(2) + lat1.cos() * lat2.cos() * half_lon.sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+}
@@ -0,0 +1,246 @@
+use std::ops::ControlFlow;
+
+use crate::profile::VehicleProfile;
+use crate::queue::{DefaultQueue, PriorityQueue};
+use crate::{EdgeTo, Map, SearchResult, ShortestPathTree, UNREACHED};
+
+/// Directed graph with non-negative integer edge weights, which is all the searches need.
+pub trait Graph {
+    fn node_count(&self) -> usize;
+
+    /// (neighbour, weight) of every edge out of `node`.
+    fn neighbours(&self, node: usize) -> impl Iterator<Item = (usize, u32)> + '_;
+}
+
+/// The map as it is, weighted by drive time.
+impl Graph for Map {
+    fn node_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    fn neighbours(&self, node: usize) -> impl Iterator<Item = (usize, u32)> + '_ {
+        self.edges[node]
+            .iter()
+            .map(|e| (e.to as usize, e.drive_time))
+    }
+}
+
+/// Any graph with every edge turned around, for searches towards a node.
+pub struct Reversed {
+    incoming: Vec<Vec<(u32, u32)>>,
+}
+
+impl Reversed {
+    pub fn new(graph: &impl Graph) -> Self {
+        let mut incoming = vec![Vec::new(); graph.node_count()];
+        for from in 0..graph.node_count() {
+            for (to, weight) in graph.neighbours(from) {
+                incoming[to].push((from as u32, weight));
+            }
+        }
+        Self { incoming }
+    }
+}
+
+impl Graph for Reversed {
+    fn node_count(&self) -> usize {
+        self.incoming.len()
+    }
+
+    fn neighbours(&self, node: usize) -> impl Iterator<Item = (usize, u32)> + '_ {
+        self.incoming[node]
+            .iter()
+            .map(|&(from, weight)| (from as usize, weight))
+    }
+}
+
+/// The map with the weight of every edge given by `cost(from, edge)`, which leaves the edge
+/// out by returning None.
+pub struct Filtered<'a, F> {
+    map: &'a Map,
+    cost: F,
+}
+
+impl<'a, F: Fn(usize, &EdgeTo) -> Option<u32>> Filtered<'a, F> {
+    pub fn new(map: &'a Map, cost: F) -> Self {
+        Self { map, cost }
+    }
+}
+
+impl<F: Fn(usize, &EdgeTo) -> Option<u32>> Graph for Filtered<'_, F> {
+    fn node_count(&self) -> usize {
+        self.map.edges.len()
+    }
+
+    fn neighbours(&self, node: usize) -> impl Iterator<Item = (usize, u32)> + '_ {
+        self.map.edges[node]
+            .iter()
+            .filter_map(move |e| Some((e.to as usize, (self.cost)(node, e)?)))
+    }
+}
+
+/// The map weighted by the drive times of a vehicle, without the copy `get_profile_copy` makes.
+pub struct ProfileWeighted<'a> {
+    map: &'a Map,
+    profile: &'a VehicleProfile,
+}
+
+impl<'a> ProfileWeighted<'a> {
+    pub fn new(map: &'a Map, profile: &'a VehicleProfile) -> Self {
+        Self { map, profile }
+    }
+}
+
+impl Graph for ProfileWeighted<'_> {
+    fn node_count(&self) -> usize {
+        self.map.edges.len()
+    }
+
+    fn neighbours(&self, node: usize) -> impl Iterator<Item = (usize, u32)> + '_ {
+        self.map.edges[node]
+            .iter()
+            .map(|e| (e.to as usize, self.profile.drive_time(e)))
+    }
+}
+
+/// Dijkstra, or A* with a heuristic, on any graph. The arrays are kept between runs and only
+/// the nodes the last run reached are reset, so many short searches cost no more than they
+/// explore.
+pub struct Search<Q = DefaultQueue> {
+    distances: Vec<u32>,
+    previous: Vec<Option<usize>>,
+    /// Nodes the last run reached.
+    touched: Vec<usize>,
+    priority_queue: Q,
+}
+
+impl<Q: PriorityQueue<u32>> Search<Q> {
+    pub fn new(node_count: usize) -> Self {
+        Self {
+            distances: vec![UNREACHED; node_count],
+            previous: vec![None; node_count],
+            touched: Vec::new(),
+            priority_queue: Q::new(node_count),
+        }
+    }
+
+    /// Searches from all the `sources` at once, settling nodes by drive time plus
+    /// `heuristic(node)`. The drive times are shortest as long as the heuristic never
+    /// overestimates the drive time left and never drops by more than an edge's weight along
+    /// it, like the landmark bounds of ALT. Dijkstra is `|_| 0`.
+    ///
+    /// `settle` gets every node and its drive time as it is settled, before its edges are
+    /// followed, and stops the search by returning `ControlFlow::Break`.
+    pub fn run(
+        &mut self,
+        graph: &impl Graph,
+        sources: &[usize],
+        heuristic: impl Fn(usize) -> u32,
+        settle: impl FnMut(usize, u32) -> ControlFlow<()>,
+    ) {
+        let sources = sources.iter().map(|source| (*source, 0));
+        self.run_from(graph, sources, heuristic, settle);
+    }
+
+    /// Like `run`, but every source starts at the drive time it comes with instead of 0, as if
+    /// it were reached from one node before them all. A source listed twice keeps the lowest.
+    pub fn run_from(
+        &mut self,
+        graph: &impl Graph,
+        sources: impl IntoIterator<Item = (usize, u32)>,
+        heuristic: impl Fn(usize) -> u32,
+        mut settle: impl FnMut(usize, u32) -> ControlFlow<()>,
+    ) {
+        for node in self.touched.drain(..) {
+            self.distances[node] = UNREACHED;
+            self.previous[node] = None;
+        }
+        self.priority_queue.clear();
+        for (source, start) in sources {
+            if start >= self.distances[source] {
+                continue;
+            }
+            if self.distances[source] == UNREACHED {
+                self.touched.push(source);
+            }
+            self.distances[source] = start;
+            self.priority_queue
+                .push(source, start.saturating_add(heuristic(source)));
+        }
+
+        while let Some((number, _)) = self.priority_queue.pop() {
+            let distance = self.distances[number];
+            if settle(number, distance).is_break() {
+                break;
+            }
+            for (to, weight) in graph.neighbours(number) {
+                // Weights up to u32::MAX must neither wrap around nor look reachable
+                let alt = distance.saturating_add(weight);
+                if alt >= UNREACHED {
+                    continue;
+                }
+                if alt < self.distances[to] {
+                    if self.distances[to] == UNREACHED {
+                        self.touched.push(to);
+                    }
+                    self.distances[to] = alt;
+                    self.previous[to] = Some(number);
+                    self.priority_queue
+                        .push(to, alt.saturating_add(heuristic(to)));
+                }
+            }
+        }
+    }
+
+    /// Drive time of the last run to the node, or None if it was not reached. Only final for
+    /// settled nodes when the run was stopped early.
+    pub fn distance(&self, node: usize) -> Option<u32> {
+        Some(self.distances[node]).filter(|d| *d < UNREACHED)
+    }
+
+    /// Path of the last run from the source it reached the node from to the node, both
+    /// included.
+    pub fn path_to(&self, node: usize) -> Option<Vec<u32>> {
+        self.distance(node)?;
+        let mut path = vec![node as u32];
+        let mut current = node;
+        while let Some(previous) = self.previous[current] {
+            path.push(previous as u32);
+            current = previous;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    pub fn into_tree(self) -> ShortestPathTree {
+        ShortestPathTree {
+            distances: self.distances,
+            previous: self.previous,
+        }
+    }
+}
+
+/// Search from `source` that stops once `goal` is settled. Returns None if the goal can not be
+/// reached.
+pub fn point_to_point<Q: PriorityQueue<u32>>(
+    graph: &impl Graph,
+    source: usize,
+    goal: usize,
+    heuristic: impl Fn(usize) -> u32,
+) -> Option<SearchResult> {
+    let mut search = Search::<Q>::new(graph.node_count());
+    let mut visited = Vec::new();
+    search.run(graph, &[source], heuristic, |node, _| {
+        visited.push(node as u32);
+        if node == goal {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    Some(SearchResult {
+        drive_time: search.distance(goal)?,
+        path: search.path_to(goal)?,
+        visited,
+    })
+}
@@ -0,0 +1,379 @@
+use std::cmp::Reverse;
+
+use crate::benchmark::Random;
+use crate::queue::{DefaultQueue, PriorityQueue};
+use crate::{full_dijkstra, get_byte_array_from_u32, get_u32_from_byte_array};
+use crate::{EdgeTo, Map, ShortestPathTree, UNREACHED};
+
+/// Marks the label files written by `HubLabels::to_bytes`.
+const FILE_MAGIC: &[u8; 4] = b"HUB1";
+
+/// Order in which nodes become hubs. Earlier hubs prune the searches of later ones, so the
+/// nodes on many shortest paths should come first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HubOrder {
+    /// Highest in + out degree first. Cheap, but road junctions all have about the same degree.
+    Degree,
+    /// Nodes with the most descendants in the shortest path trees of `samples` random roots
+    /// first, degree breaking ties. Finds the main roads, which gives much smaller labels.
+    Coverage { samples: usize, seed: u64 },
+}
+
+impl HubOrder {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "degree" => Some(Self::Degree),
+            "coverage" => Some(Self::coverage()),
+            _ => None,
+        }
+    }
+
+    pub fn coverage() -> Self {
+        Self::Coverage {
+            samples: 16,
+            seed: 42,
+        }
+    }
+
+    /// Node numbers, most important first.
+    pub fn nodes(&self, map: &Map) -> Vec<u32> {
+        let mut degrees: Vec<usize> = map.edges.iter().map(Vec::len).collect();
+        for edge in map.edges.iter().flatten() {
+            degrees[edge.to as usize] += 1;
+        }
+        let mut coverage = vec![0_u64; map.edges.len()];
+        if let Self::Coverage { samples, seed } = *self {
+            let mut random = Random::new(seed);
+            for _ in 0..samples.min(map.edges.len()) {
+                let root = random.below(map.edges.len()) as u32;
+                let ShortestPathTree {
+                    distances,
+                    previous,
+                    ..
+                } = full_dijkstra(map, root);
+                // Children are further away than their parents, so counting from the furthest
+                // node adds every subtree before its root is added to its parent
+                let mut reached: Vec<usize> = (0..distances.len())
+                    .filter(|n| distances[*n] < UNREACHED)
+                    .collect();
+                reached.sort_unstable_by_key(|n| Reverse(distances[*n]));
+                let mut descendants = vec![1_u64; map.edges.len()];
+                for node in reached {
+                    coverage[node] += descendants[node];
+                    if let Some(parent) = previous[node] {
+                        descendants[parent] += descendants[node];
+                    }
+                }
+            }
+        }
+        let mut nodes: Vec<u32> = (0..map.edges.len() as u32).collect();
+        nodes.sort_by_key(|n| {
+            let n = *n as usize;
+            (Reverse(coverage[n]), Reverse(degrees[n]))
+        });
+        nodes
+    }
+}
+
+/// Answer to `HubLabels::query`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HubQuery {
+    /// Centiseconds.
+    pub drive_time: u32,
+    /// Rank of the hub on the route, where the labels meet.
+    pub hub_rank: u32,
+    /// Label entries compared, the work the query did.
+    pub compared: usize,
+}
+
+/// Labels of every node, sorted by hub rank. Entry `i` of node `n` is at index
+/// `offsets[n] + i` of the other vectors.
+struct Labels {
+    offsets: Vec<usize>,
+    ranks: Vec<u32>,
+    distances: Vec<u32>,
+    /// Neighbour on the shortest path towards the hub: the next node for forward labels, the
+    /// previous node for backward labels. The hub itself stores itself.
+    hops: Vec<u32>,
+}
+
+impl Labels {
+    fn from_lists(lists: Vec<Vec<(u32, u32, u32)>>) -> Self {
+        let total = lists.iter().map(Vec::len).sum();
+        let mut labels = Self {
+            offsets: Vec::with_capacity(lists.len() + 1),
+            ranks: Vec::with_capacity(total),
+            distances: Vec::with_capacity(total),
+            hops: Vec::with_capacity(total),
+        };
+        labels.offsets.push(0);
+        for list in lists {
+            for (rank, distance, hop) in list {
+                labels.ranks.push(rank);
+                labels.distances.push(distance);
+                labels.hops.push(hop);
+            }
+            labels.offsets.push(labels.ranks.len());
+        }
+        labels
+    }
+
+    fn range(&self, node: usize) -> std::ops::Range<usize> {
+        self.offsets[node]..self.offsets[node + 1]
+    }
+
+    /// Hop of the node's entry for the hub.
+    fn hop(&self, node: usize, rank: u32) -> Option<u32> {
+        let range = self.range(node);
+        let i = self.ranks[range.clone()].binary_search(&rank).ok()?;
+        Some(self.hops[range.start + i])
+    }
+}
+
+/// Pruned landmark labeling (Akiba, Iwata and Yoshida, 2013) on drive times.
+///
+/// Every node has a forward label of (hub, drive time to the hub) and a backward label of
+/// (hub, drive time from the hub), such that some hub in both labels of a source and target
+/// lies on a shortest path between them. A query merges the two sorted labels instead of
+/// searching the map. Each entry also stores the next node towards its hub, so the path can be
+/// unpacked without a search.
+pub struct HubLabels {
+    /// Node number of every hub rank.
+    hubs: Vec<u32>,
+    forward: Labels,
+    backward: Labels,
+}
+
+/// Arrays for the pruned searches, reset after each one by walking the nodes it touched.
+struct Scratch {
+    /// Drive times between the current hub and the hubs in its own other label, by rank.
+    hub_distances: Vec<u32>,
+    distances: Vec<u32>,
+    parents: Vec<u32>,
+    touched: Vec<usize>,
+    priority_queue: DefaultQueue,
+}
+
+impl Scratch {
+    /// Pruned Dijkstra from `hub` over `edges`, adding (rank, distance, parent) to the labels
+    /// of every node it settles. `other` holds the labels a query combines with `labels`, so a
+    /// node is pruned when the hubs found so far already give a route at least as short.
+    fn pruned_search(
+        &mut self,
+        edges: &[Vec<EdgeTo>],
+        hub: usize,
+        rank: u32,
+        labels: &mut [Vec<(u32, u32, u32)>],
+        other: &[Vec<(u32, u32, u32)>],
+    ) {
+        for &(r, distance, _) in &other[hub] {
+            self.hub_distances[r as usize] = distance;
+        }
+        self.distances[hub] = 0;
+        self.parents[hub] = hub as u32;
+        self.touched.push(hub);
+        self.priority_queue.push(hub, 0);
+        while let Some((number, distance)) = self.priority_queue.pop() {
+            let known = labels[number]
+                .iter()
+                .map(|&(r, d, _)| self.hub_distances[r as usize].saturating_add(d))
+                .min()
+                .unwrap_or(u32::MAX);
+            if known <= distance {
+                continue;
+            }
+            labels[number].push((rank, distance, self.parents[number]));
+            for edge in &edges[number] {
+                let to = edge.to as usize;
+                let alt = distance.saturating_add(edge.drive_time);
+                if alt < self.distances[to] {
+                    if self.distances[to] == UNREACHED {
+                        self.touched.push(to);
+                    }
+                    self.distances[to] = alt;
+                    self.parents[to] = number as u32;
+                    self.priority_queue.push(to, alt);
+                }
+            }
+        }
+        for node in self.touched.drain(..) {
+            self.distances[node] = UNREACHED;
+        }
+        for &(r, _, _) in &other[hub] {
+            self.hub_distances[r as usize] = UNREACHED;
+        }
+    }
+}
+
+impl HubLabels {
+    /// Runs a forward and a backward pruned search from every node in `order`. `progress` is
+    /// called with the number of hubs done after each one.
+    pub fn build(map: &Map, order: &[u32], mut progress: impl FnMut(usize)) -> Self {
+        let node_count = map.edges.len();
+        let reverse_edges = map.get_reverse_copy().edges;
+        let mut forward = vec![Vec::new(); node_count];
+        let mut backward = vec![Vec::new(); node_count];
+        let mut scratch = Scratch {
+            hub_distances: vec![UNREACHED; node_count],
+            distances: vec![UNREACHED; node_count],
+            parents: vec![0; node_count],
+            touched: Vec::new(),
+            priority_queue: DefaultQueue::new(node_count),
+        };
+
+        for (rank, &hub) in order.iter().enumerate() {
+            // Drive times from the hub go in backward labels, to the hub in forward labels
+            let (hub, rank) = (hub as usize, rank as u32);
+            scratch.pruned_search(&map.edges, hub, rank, &mut backward, &forward);
+            scratch.pruned_search(&reverse_edges, hub, rank, &mut forward, &backward);
+            progress(rank as usize + 1);
+        }
+        Self {
+            hubs: order.to_vec(),
+            forward: Labels::from_lists(forward),
+            backward: Labels::from_lists(backward),
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.hubs.len()
+    }
+
+    /// Average number of entries in a forward and a backward label.
+    pub fn average_label_size(&self) -> f64 {
+        let entries = self.forward.ranks.len() + self.backward.ranks.len();
+        entries as f64 / (2 * self.hubs.len().max(1)) as f64
+    }
+
+    /// Returns None if the target can not be reached.
+    pub fn query(&self, source: usize, target: usize) -> Option<HubQuery> {
+        let out = self.forward.range(source);
+        let into = self.backward.range(target);
+        let (mut i, mut j) = (out.start, into.start);
+        let mut best: Option<(u32, u32)> = None;
+        while i < out.end && j < into.end {
+            let (a, b) = (self.forward.ranks[i], self.backward.ranks[j]);
+            if a == b {
+                // Like the searches, a route that adds up past UNREACHED does not exist
+                let distance = self.forward.distances[i].saturating_add(self.backward.distances[j]);
+                if distance < UNREACHED && best.is_none_or(|(d, _)| distance < d) {
+                    best = Some((distance, a));
+                }
+            }
+            if a <= b {
+                i += 1;
+            }
+            if b <= a {
+                j += 1;
+            }
+        }
+        let compared = (i - out.start) + (j - into.start);
+        best.map(|(drive_time, hub_rank)| HubQuery {
+            drive_time,
+            hub_rank,
+            compared,
+        })
+    }
+
+    /// Shortest path from source to target, both included, by following the hops of the labels
+    /// to and from the hub where they meet.
+    pub fn path(&self, source: usize, target: usize) -> Option<Vec<u32>> {
+        let rank = self.query(source, target)?.hub_rank;
+        let hub = self.hubs[rank as usize];
+        let mut path = vec![source as u32];
+        while *path.last()? != hub {
+            path.push(self.forward.hop(*path.last()? as usize, rank)?);
+        }
+        let mut to_target = vec![target as u32];
+        while *to_target.last()? != hub {
+            to_target.push(self.backward.hop(*to_target.last()? as usize, rank)?);
+        }
+        to_target.pop();
+        path.extend(to_target.into_iter().rev());
+        Some(path)
+    }
+
+    /// Magic, node count and the hub order, then the forward and backward labels. Every label
+    /// is its length followed by (rank increase, drive time, hop) per entry, all as LEB128
+    /// varints since most of them are small.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = FILE_MAGIC.to_vec();
+        bytes.extend(get_byte_array_from_u32(self.hubs.len() as u32));
+        for &hub in &self.hubs {
+            push_varint(&mut bytes, hub);
+        }
+        for labels in [&self.forward, &self.backward] {
+            for node in 0..self.hubs.len() {
+                let range = labels.range(node);
+                push_varint(&mut bytes, range.len() as u32);
+                let mut previous_rank = 0;
+                for i in range {
+                    push_varint(&mut bytes, labels.ranks[i] - previous_rank);
+                    push_varint(&mut bytes, labels.distances[i]);
+                    push_varint(&mut bytes, labels.hops[i]);
+                    previous_rank = labels.ranks[i];
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Returns None if the bytes are not a complete label file.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 8 || &bytes[0..4] != FILE_MAGIC {
+            return None;
+        }
+        let node_count = get_u32_from_byte_array(&bytes[4..8]) as usize;
+        let mut position = 8;
+        let mut hubs = Vec::with_capacity(node_count);
+        for _ in 0..node_count {
+            hubs.push(read_varint(bytes, &mut position)?);
+        }
+        let mut read_labels = || {
+            let mut lists = Vec::with_capacity(node_count);
+            for _ in 0..node_count {
+                let length = read_varint(bytes, &mut position)?;
+                let mut list = Vec::with_capacity(length as usize);
+                let mut rank = 0;
+                for _ in 0..length {
+                    rank += read_varint(bytes, &mut position)?;
+                    let distance = read_varint(bytes, &mut position)?;
+                    list.push((rank, distance, read_varint(bytes, &mut position)?));
+                }
+                lists.push(list);
+            }
+            Some(Labels::from_lists(lists))
+        };
+        let forward = read_labels()?;
+        let backward = read_labels()?;
+        if position != bytes.len() {
+            return None;
+        }
+        Some(Self {
+            hubs,
+            forward,
+            backward,
+        })
+    }
+}
+
+fn push_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], position: &mut usize) -> Option<u32> {
+    let mut value = 0_u32;
+    for shift in (0..35).step_by(7) {
+        let byte = *bytes.get(*position)?;
+        *position += 1;
+        value |= ((byte & 0x7f) as u32).checked_shl(shift)?;
+        if byte < 0x80 {
+            return Some(value);
+        }
+    }
+    None
+}
@@ -0,0 +1,688 @@
+//! Shortest paths on road networks: Dijkstra, ALT with landmarks, hub labels and arc flags,
+//! points of interest, electric vehicle routing, map matching and exports.
+//!
+//! A map is read from the node, edge and point of interest files sharing a prefix. Searches
+//! take node numbers, which are the line numbers in the node file, and return their results as
+//! structs with drive times in centiseconds.
+//!
+//! ```no_run
+//! use pathfinding::queue::QueueKind;
+//! use pathfinding::waypoints::Landmarks;
+//! use pathfinding::{alt, get_waypoints, travel_path_to_csv, Map};
+//!
+//! let map = Map::load("norden").expect("Could not load map");
+//! let landmarks = Landmarks::farthest();
+//! let waypoints =
+//!     get_waypoints(&map, "norden_waypoints.bin", &landmarks, QueueKind::Quad, 4, false)
+//!         .expect("Could not load or create waypoints");
+//! if let Some(result) = alt(&map, &waypoints, 3292784, 7352330) {
+//!     println!("{} centiseconds, {} nodes settled", result.drive_time, result.visited.len());
+//!     travel_path_to_csv(map.path_coordinates(&result.path), "path.csv")
+//!         .expect("Could not write result to file");
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
+use std::ops::ControlFlow;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+use arc_flags::ArcFlags;
+use duration::{Language, TravelDuration};
+use graph::{point_to_point, Search};
+use hub_labels::{HubLabels, HubOrder};
+use queue::{DefaultQueue, LazyBinaryHeap, PriorityQueue, QuadHeap, QueueKind, RadixHeap};
+use waypoints::{Landmarks, Waypoints};
+
+pub mod arc_flags;
+pub mod astar;
+pub mod benchmark;
+pub mod bidirectional;
+pub mod components;
+pub mod detour;
+pub mod directions;
+pub mod duration;
+pub mod ev;
+pub mod extract;
+pub mod geo;
+pub mod graph;
+pub mod hub_labels;
+pub mod matching;
+pub mod matrix;
+pub mod meeting;
+pub mod osm;
+pub mod overlay;
+pub mod profile;
+pub mod queue;
+pub mod raster;
+pub mod spatial;
+pub mod svg;
+#[cfg(test)]
+mod tests;
+pub mod validate;
+pub mod waypoints;
+
+/// Drive time `full_dijkstra` leaves on nodes it never reached.
+pub const UNREACHED: u32 = u32::MAX / 2;
+
+/// Directed road from the node it is stored under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeTo {
+    pub to: u32,
+    /// Centiseconds.
+    pub drive_time: u32,
+    /// Meters.
+    pub length: u32,
+    /// km/h.
+    pub speed_limit: u16,
+}
+
+impl EdgeTo {
+    pub fn new(to: u32, drive_time: u32, length: u32, speed_limit: u16) -> Self {
+        Self {
+            to,
+            drive_time,
+            length,
+            speed_limit,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Node {
+    pub id: u32,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Node {
+    pub fn new(id: u32, latitude: f64, longitude: f64) -> Self {
+        Self {
+            id,
+            latitude,
+            longitude,
+        }
+    }
+}
+
+/// Road network with the outgoing edges of every node, and the points of interest as
+/// node -> (category bits, name).
+#[derive(Clone)]
+pub struct Map {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Vec<EdgeTo>>,
+    pub points_of_interest: HashMap<u32, (u8, String)>,
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            points_of_interest: HashMap::new(),
+        }
+    }
+
+    pub fn from_nodes_edges_and_poi(
+        nodes: Vec<Node>,
+        edges: Vec<Vec<EdgeTo>>,
+        points_of_interest: HashMap<u32, (u8, String)>,
+    ) -> Self {
+        Self {
+            nodes,
+            edges,
+            points_of_interest,
+        }
+    }
+
+    /// Reads "PREFIX_noder.txt", "PREFIX_kanter.txt" and "PREFIX_interessepkt.txt".
+    pub fn load(prefix: &str) -> io::Result<Self> {
+        let (node_path, edge_path, poi_path) = map_paths(prefix);
+        get_map_from_paths(&node_path, &edge_path, &poi_path)
+    }
+
+    pub fn get_coordinates_from_node(&self, node: usize) -> (f64, f64) {
+        let n = &self.nodes[node];
+        (n.latitude, n.longitude)
+    }
+
+    /// (latitude, longitude) of every node on a path, for `travel_path_to_csv`.
+    pub fn path_coordinates(&self, path: &[u32]) -> Vec<(f64, f64)> {
+        path.iter()
+            .map(|n| self.get_coordinates_from_node(*n as usize))
+            .collect()
+    }
+
+    /// Copy with every edge turned around, for searches towards a node.
+    pub fn get_reverse_copy(&self) -> Self {
+        let mut edges = vec![Vec::new(); self.edges.len()];
+        for i in 0..self.edges.len() {
+            for edge in &self.edges[i] {
+                edges[edge.to as usize].push(EdgeTo::new(
+                    i as u32,
+                    edge.drive_time,
+                    edge.length,
+                    edge.speed_limit,
+                ));
+            }
+        }
+        Map::from_nodes_edges_and_poi(self.nodes.clone(), edges, self.points_of_interest.clone())
+    }
+
+    /// Name of the point of interest at the node, or "Custom Waypoint".
+    pub fn get_name(&self, node_id: u32) -> String{
+        self.points_of_interest
+        .get(&node_id)
+        .get_or_insert(&(0_u8, "Custom Waypoint".to_owned()))
+        .1.to_owned()
+    }
+}
+
+/// Shortest route found by a point-to-point search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// Centiseconds.
+    pub drive_time: u32,
+    /// Nodes from the source to the goal, both included.
+    pub path: Vec<u32>,
+    /// Nodes in the order the search settled them, the goal last.
+    pub visited: Vec<u32>,
+}
+
+/// Drive times from the sources of a search to every node, with the node before every reached
+/// node on its shortest path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortestPathTree {
+    /// Centiseconds, `UNREACHED` for nodes the search never reached.
+    pub distances: Vec<u32>,
+    pub previous: Vec<Option<usize>>,
+}
+
+impl ShortestPathTree {
+    /// Returns None if the node was never reached.
+    pub fn distance_to(&self, node: usize) -> Option<u32> {
+        Some(self.distances[node]).filter(|d| *d < UNREACHED)
+    }
+
+    /// Shortest path from a source to the node, both included.
+    pub fn path_to(&self, node: usize) -> Option<Vec<u32>> {
+        self.distance_to(node)?;
+        let mut path = vec![node as u32];
+        let mut current = node;
+        while let Some(previous) = self.previous[current] {
+            path.push(previous as u32);
+            current = previous;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// A* with landmark lower bounds. Returns None if the goal can not be reached.
+pub fn alt(map: &Map, waypoints: &Waypoints, source: usize, goal: usize) -> Option<SearchResult> {
+    alt_with_queue::<DefaultQueue>(map, waypoints, source, goal)
+}
+
+pub fn alt_with_queue<Q: PriorityQueue<u32>>(
+    map: &Map,
+    waypoints: &Waypoints,
+    source: usize,
+    goal: usize,
+) -> Option<SearchResult> {
+    point_to_point::<Q>(map, source, goal, |node| waypoints.lower_bound(node, goal))
+}
+
+/// Dijkstra that stops at the goal. Returns None if the goal can not be reached.
+pub fn closest_dijkstra(map: &Map, from: usize, to: usize) -> Option<SearchResult> {
+    closest_dijkstra_with_queue::<DefaultQueue>(map, from, to)
+}
+
+pub fn closest_dijkstra_with_queue<Q: PriorityQueue<u32>>(
+    map: &Map,
+    from: usize,
+    to: usize,
+) -> Option<SearchResult> {
+    point_to_point::<Q>(map, from, to, |_| 0)
+}
+
+/// The `amount` points of interest with all the bits of `category` closest to the source,
+/// closest first.
+pub fn category_based_dijkstra(map: &Map, source: usize, category: u8, amount: u32) -> Vec<u32> {
+    category_based_dijkstra_with_queue::<DefaultQueue>(map, source, category, amount)
+}
+
+pub fn category_based_dijkstra_with_queue<Q: PriorityQueue<u32>>(
+    map: &Map,
+    source: usize,
+    category: u8,
+    amount: u32,
+) -> Vec<u32> {
+    let mut results = Vec::with_capacity(amount as usize);
+    Search::<Q>::new(map.edges.len()).run(map, &[source], |_| 0, |number, _| {
+        if let Some(poi) = map.points_of_interest.get(&(number as u32)) {
+            if poi.0 & category == category {
+                results.push(number as u32)
+            }
+        }
+        if results.len() >= amount as usize {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    results
+}
+
+/// Walks `previous` back from the goal. Returns None if the goal was never reached.
+pub fn path_from_previous(
+    previous: &[Option<usize>],
+    source: usize,
+    goal: usize,
+) -> Option<Vec<u32>> {
+    let mut path = vec![goal as u32];
+    let mut current = goal;
+    while current != source {
+        current = previous[current]?;
+        path.push(current as u32);
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Drive times from the source to every node.
+pub fn full_dijkstra(map: &Map, source: u32) -> ShortestPathTree {
+    full_dijkstra_with_queue::<DefaultQueue>(map, source)
+}
+
+pub fn full_dijkstra_with(map: &Map, source: u32, queue: QueueKind) -> ShortestPathTree {
+    match queue {
+        QueueKind::Binary => full_dijkstra_with_queue::<LazyBinaryHeap<u32>>(map, source),
+        QueueKind::Quad => full_dijkstra_with_queue::<QuadHeap<u32>>(map, source),
+        QueueKind::Radix => full_dijkstra_with_queue::<RadixHeap>(map, source),
+    }
+}
+
+pub fn full_dijkstra_with_queue<Q: PriorityQueue<u32>>(map: &Map, source: u32) -> ShortestPathTree {
+    let mut search = Search::<Q>::new(map.edges.len());
+    search.run(map, &[source as usize], |_| 0, |_, _| ControlFlow::Continue(()));
+    search.into_tree()
+}
+
+fn node_from_string(l: Vec<&str>) -> Node {
+    Node::new(
+        l[0].parse().expect("Could not parse to node"),
+        l[1].parse().expect("Could not parse to node"),
+        l[2].parse().expect("Could not parse to node"),
+    )
+}
+
+fn edge_from_string(l: Vec<&str>) -> EdgeTo {
+    EdgeTo::new(
+        l[1].parse().expect("Could not parse to node"),
+        l[2].parse().expect("Could not parse to node"),
+        l[3].parse().expect("Could not parse to node"),
+        l[4].parse().expect("Could not parse to node"),
+    )
+}
+
+pub fn get_map_from_paths(node_path: &str, edge_path: &str, poi_path: &str) -> io::Result<Map> {
+    let node_file = File::open(node_path)?;
+    let edge_file = File::open(edge_path)?;
+    let poi_file = File::open(poi_path)?;
+
+    let mut node_reader = BufReader::new(node_file);
+    let mut node_first_line = String::new();
+    node_reader.read_line(&mut node_first_line)?;
+    let node_lines: usize = node_first_line
+        .trim()
+        .parse()
+        .expect("Could not parse First line");
+    let nodes: Vec<Node> = node_reader
+        .lines()
+        .map(|line| {
+            node_from_string(
+                line.expect("Failed to read line")
+                    .split_whitespace()
+                    .collect(),
+            )
+        })
+        .collect();
+
+    assert_eq!(nodes.len(), node_lines);
+    assert!(
+        nodes.iter().enumerate().all(|(i, n)| n.id as usize == i),
+        "Node ids must match their line number"
+    );
+
+    let mut edge_reader = BufReader::new(edge_file);
+    let mut edge_first_line = String::new();
+    edge_reader.read_line(&mut edge_first_line)?;
+    let edges_len: usize = edge_first_line
+        .trim()
+        .parse()
+        .expect("Could not parse First line");
+    let mut edges = vec![Vec::new(); nodes.len()];
+
+    for line in edge_reader.lines() {
+        let line = line.expect("Could not parse line");
+        let l: Vec<&str> = line.split_whitespace().collect();
+        edges[l[0].parse::<usize>().unwrap()].push(edge_from_string(l));
+    }
+
+    assert_eq!(edges.iter().map(Vec::len).sum::<usize>(), edges_len);
+
+    let mut poi_reader = BufReader::new(poi_file);
+    let mut poi_first_line = String::new();
+    poi_reader.read_line(&mut poi_first_line)?;
+    let poi_len: usize = poi_first_line
+        .trim()
+        .parse()
+        .expect("Could not parse First line");
+
+    let mut poi: HashMap<u32, (u8, String)> = HashMap::with_capacity(poi_len);
+    for line in poi_reader.lines() {
+        let line = line.expect("Could not parse line");
+        let l: Vec<&str> = line.split("\t").collect();
+        poi.insert(
+            l[0].parse().expect("Could not parse line"),
+            (
+                l[1].parse().expect("Could not parse line"),
+                l[2][1..(l[2].len() - 1)].to_string(),
+            ),
+        );
+    }
+    Ok(Map::from_nodes_edges_and_poi(nodes, edges, poi))
+}
+
+/// Writes the coordinates as a CSV file with a "Latitude,Longitude" header.
+pub fn travel_path_to_csv(travel_path: Vec<(f64, f64)>, file_path: &str) -> io::Result<()> {
+    let mut file = File::create(file_path)?;
+    file.write_all("Latitude,Longitude\n".as_bytes())?;
+    for p in travel_path {
+        file.write_all(format!("{},{}\n", p.0, p.1).as_bytes())?;
+    }
+    Ok(())
+}
+
+/// English prose like "2 hours, 5 minutes and 3 seconds.", see `TravelDuration` for the
+/// other formats.
+pub fn centi_seconds_to_time_format(centi_seconds: usize) -> String {
+    TravelDuration::from_centi_seconds(centi_seconds as u64).to_text(Language::English)
+}
+
+fn get_file_as_bytes(path: &str) -> io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+    reader.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn get_u32_from_byte_array(bytes: &[u8]) -> u32 {
+    assert_eq!(bytes.len(), 4);
+    let mut num = 0;
+
+    num |= bytes[3] as u32;
+    num |= (bytes[2] as u32) << 8;
+    num |= (bytes[1] as u32) << 16;
+    num |= (bytes[0] as u32) << 24;
+
+    num
+}
+
+fn get_byte_array_from_u32(input: u32) -> [u8; 4] {
+    let b1: u8 = ((input >> 24) & 0xff) as u8;
+    let b2: u8 = ((input >> 16) & 0xff) as u8;
+    let b3: u8 = ((input >> 8) & 0xff) as u8;
+    let b4: u8 = (input & 0xff) as u8;
+    [b1, b2, b3, b4]
+}
+
+enum WaypointProgress {
+    SearchDone,
+    LandmarkDone(Vec<u8>),
+}
+
+/// Calculates the landmarks on at most `workers` threads. Fails with `InvalidInput` if there are
+/// no sources or one of them is not a node of the map. Every landmark is written as soon as
+/// it is done, to a temporary file that replaces `path` once all landmarks are written.
+pub fn create_waypoints(
+    map: &Map,
+    sources: &[u32],
+    path: &str,
+    queue: QueueKind,
+    workers: usize,
+    quantised: bool,
+) -> io::Result<()> {
+    if sources.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "No landmarks given"));
+    }
+    if let Some(source) = sources.iter().find(|s| **s as usize >= map.edges.len()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Landmark {} is not a node of the map", source),
+        ));
+    }
+    let reverse_map = &map.get_reverse_copy();
+    let temporary_path = format!("{}.tmp", path);
+    let mut writer = BufWriter::new(File::create(&temporary_path)?);
+    writer.write_all(&Waypoints::header_bytes(
+        sources.len(),
+        map.edges.len(),
+        quantised,
+    ))?;
+    let next_source = &AtomicUsize::new(0);
+    let cancelled = &AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+    let timer = Instant::now();
+
+    let result = thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, sources.len().max(1)) {
+            let sender = sender.clone();
+            scope.spawn(move || {
+                while !cancelled.load(AtomicOrdering::Relaxed) {
+                    let index = next_source.fetch_add(1, AtomicOrdering::Relaxed);
+                    let Some(&source) = sources.get(index) else {
+                        break;
+                    };
+                    let tree_to = full_dijkstra_with(map, source, queue);
+                    let _ = sender.send(WaypointProgress::SearchDone);
+                    let tree_from = full_dijkstra_with(reverse_map, source, queue);
+                    let bytes = Waypoints::landmark_bytes(
+                        source,
+                        &tree_to.distances,
+                        &tree_from.distances,
+                        quantised,
+                    );
+                    let _ = sender.send(WaypointProgress::LandmarkDone(bytes));
+                }
+            });
+        }
+        drop(sender);
+
+        let total = sources.len() * 2;
+        let mut done = 0;
+        for progress in receiver {
+            done += 1;
+            let elapsed = timer.elapsed().as_secs_f64();
+            println!(
+                "Landmark searches: {}/{} done using {:?}, {:.1} s elapsed, about {:.0} s left",
+                done,
+                total,
+                queue,
+                elapsed,
+                elapsed / done as f64 * (total - done) as f64
+            );
+            if let WaypointProgress::LandmarkDone(bytes) = progress {
+                if let Err(error) = writer.write_all(&bytes) {
+                    cancelled.store(true, AtomicOrdering::Relaxed);
+                    return Err(error);
+                }
+            }
+        }
+        Ok(())
+    });
+
+    if let Err(error) = result.and_then(|_| writer.into_inner()?.sync_all()) {
+        let _ = fs::remove_file(&temporary_path);
+        return Err(error);
+    }
+    fs::rename(&temporary_path, path)?;
+    println!("Waypoints succsessfully written to file \"{}\"", path);
+    Ok(())
+}
+
+/// Reads the landmarks from `path`. The file is created first from `landmarks` if it is missing,
+/// in an older format, made for another map or from other nodes than `Landmarks::Nodes` lists;
+/// `quantised` only applies to a newly created file. Fails like `create_waypoints` on landmarks
+/// that are not nodes of the map.
+pub fn get_waypoints(
+    map: &Map,
+    path: &str,
+    landmarks: &Landmarks,
+    queue: QueueKind,
+    workers: usize,
+    quantised: bool,
+) -> io::Result<Waypoints> {
+    let fits = |waypoints: &Waypoints| {
+        waypoints.node_count() == map.edges.len()
+            && match landmarks {
+                Landmarks::Nodes(nodes) => waypoints.sources() == nodes,
+                Landmarks::Farthest(_) => true,
+            }
+    };
+    if let Ok(bytes) = get_file_as_bytes(path) {
+        match Waypoints::from_bytes(&bytes) {
+            Some(waypoints) if fits(&waypoints) => return Ok(waypoints),
+            _ => println!("Waypoint file \"{}\" does not fit this map, creating it again", path),
+        }
+    }
+    println!("Choosing landmarks ...");
+    let sources = landmarks.nodes(map);
+    create_waypoints(map, &sources, path, queue, workers, quantised)?;
+    Waypoints::from_bytes(&get_file_as_bytes(path)?).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "Waypoint file is incomplete")
+    })
+}
+
+/// Reads the hub labels from `path`, or builds them in the given order and writes them there
+/// first if the file is missing or made for another map.
+pub fn get_hub_labels(map: &Map, path: &str, order: HubOrder) -> io::Result<HubLabels> {
+    if let Ok(bytes) = get_file_as_bytes(path) {
+        match HubLabels::from_bytes(&bytes) {
+            Some(labels) if labels.node_count() == map.edges.len() => return Ok(labels),
+            _ => println!("Hub label file \"{}\" does not fit this map, creating it again", path),
+        }
+    }
+
+    println!("Ordering nodes by {:?} ...", order);
+    let nodes = order.nodes(map);
+    let timer = Instant::now();
+    let step = (nodes.len() / 100).max(1);
+    let labels = HubLabels::build(map, &nodes, |done| {
+        if done % step == 0 || done == nodes.len() {
+            let elapsed = timer.elapsed().as_secs_f64();
+            println!(
+                "Hub searches: {}/{} done, {:.1} s elapsed, about {:.0} s left",
+                format_number(done as isize),
+                format_number(nodes.len() as isize),
+                elapsed,
+                elapsed / done as f64 * (nodes.len() - done) as f64
+            );
+        }
+    });
+    println!("Average label size: {:.1} hubs", labels.average_label_size());
+
+    let temporary_path = format!("{}.tmp", path);
+    let mut writer = BufWriter::new(File::create(&temporary_path)?);
+    writer.write_all(&labels.to_bytes())?;
+    writer.into_inner()?.sync_all()?;
+    fs::rename(&temporary_path, path)?;
+    println!("Hub labels succsessfully written to file \"{}\"", path);
+    Ok(labels)
+}
+
+/// Reads the arc flags from `path`, or computes them for a `rows` x `columns` partition and
+/// writes them there first if the file is missing, made for another map or another partition.
+pub fn get_arc_flags(
+    map: &Map,
+    path: &str,
+    rows: usize,
+    columns: usize,
+    workers: usize,
+) -> io::Result<ArcFlags> {
+    if let Ok(bytes) = get_file_as_bytes(path) {
+        match ArcFlags::from_bytes(map, &bytes) {
+            Some(flags) if flags.grid() == (rows, columns) => return Ok(flags),
+            _ => println!("Arc flag file \"{}\" does not fit this map, creating it again", path),
+        }
+    }
+
+    let timer = Instant::now();
+    let reported = AtomicUsize::new(0);
+    let flags = ArcFlags::build(map, rows, columns, workers, |done, total| {
+        let percent = done * 100 / total;
+        if reported.fetch_max(percent, AtomicOrdering::Relaxed) < percent || done == total {
+            let elapsed = timer.elapsed().as_secs_f64();
+            println!(
+                "Boundary searches: {}/{} done, {:.1} s elapsed, about {:.0} s left",
+                format_number(done as isize),
+                format_number(total as isize),
+                elapsed,
+                elapsed / done as f64 * (total - done) as f64
+            );
+        }
+    });
+    let bytes = flags.to_bytes();
+    println!(
+        "Arc flags for {} cells took {} seconds, {:.1} % of the flags are set, {} bytes",
+        rows * columns,
+        timer.elapsed().as_millis() as f64 / 1000.0,
+        flags.flag_density() * 100.0,
+        format_number(bytes.len() as isize)
+    );
+
+    let temporary_path = format!("{}.tmp", path);
+    let mut writer = BufWriter::new(File::create(&temporary_path)?);
+    writer.write_all(&bytes)?;
+    writer.into_inner()?.sync_all()?;
+    fs::rename(&temporary_path, path)?;
+    println!("Arc flags succsessfully written to file \"{}\"", path);
+    Ok(flags)
+}
+
+
+/// Groups the digits in thousands with spaces, like "1 234 567".
+pub fn format_number(number: isize) -> String {
+    let mut str = Vec::new();
+    let mut copy = Clone::clone(&number);
+
+    while copy >= 1000 {
+        str.push(format!("{:03}", copy % 1000));
+        copy /= 1000;
+    }
+    str.push(copy.to_string());
+
+    str.into_iter().rev().collect::<Vec<String>>().join(" ")
+}
+
+/// (node, edge, point of interest) file paths of a map.
+pub fn map_paths(prefix: &str) -> (String, String, String) {
+    (
+        format!("{}_noder.txt", prefix),
+        format!("{}_kanter.txt", prefix),
+        format!("{}_interessepkt.txt", prefix),
+    )
+}
+
@@ -1,763 +1,1080 @@
-use std::cmp::{Ord, Ordering};
-use std::collections::{BinaryHeap, HashMap};
 use std::fs::File;
-use std::sync::{Arc, Mutex};
-use std::{io, thread};
-use std::io::{BufRead, BufReader, BufWriter, Read, Write};
-use std::thread::Thread;
+use std::io::{BufWriter, Write};
+use std::{env, thread};
 use std::time::Instant;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct EdgeTo {
-    to: u32,
-    drive_time: u32,
-    length: u32,
-    speed_limit: u16,
-}
-
-impl EdgeTo {
-    fn new(to: u32, drive_time: u32, length: u32, speed_limit: u16) -> Self {
-        Self {
-            to,
-            drive_time,
-            length,
-            speed_limit,
-        }
-    }
-}
-
-#[derive(Clone)]
-struct Node {
-    id: u32,
-    latitude: f64,
-    longitude: f64,
-}
-
-impl Node {
-    fn new(id: u32, latitude: f64, longitude: f64) -> Self {
-        Self {
-            id,
-            latitude,
-            longitude,
-        }
-    }
+use pathfinding::arc_flags::{self, arc_flags_dijkstra};
+use pathfinding::astar::{astar, StraightLine};
+use pathfinding::benchmark::{largest_component_nodes, random_queries, rank_queries, run_benchmark};
+use pathfinding::benchmark::{Algorithm, Random};
+use pathfinding::bidirectional::bidirectional_dijkstra;
+use pathfinding::components::strongly_connected_components;
+use pathfinding::detour::detours;
+use pathfinding::directions::{turn_by_turn, Route};
+use pathfinding::duration::{Language, Schedule, Timestamp, TravelDuration};
+use pathfinding::ev::{ev_route, ChargingModel};
+use pathfinding::extract::write_map_to_paths;
+use pathfinding::geo::{BoundingBox, Polygon};
+use pathfinding::graph::Reversed;
+use pathfinding::hub_labels::HubOrder;
+use pathfinding::matching::{match_trace, read_trace, MatchedPoint, MatchingParameters};
+use pathfinding::matrix::{self, travel_time_matrix};
+use pathfinding::meeting::{meeting_point, Objective};
+use pathfinding::spatial::GridIndex;
+use pathfinding::svg::{Projection, SvgMap};
+use pathfinding::osm::import_osm;
+use pathfinding::overlay::{alt_with_overlay, closest_dijkstra_with_overlay, Overlay};
+use pathfinding::profile::VehicleProfile;
+use pathfinding::raster::{multi_source_dijkstra, TravelTimeRaster};
+use pathfinding::queue::{LazyBinaryHeap, QuadHeap, QueueKind, RadixHeap};
+use pathfinding::validate::MapReport;
+use pathfinding::waypoints::{Landmarks, Waypoints};
+use pathfinding::{alt, alt_with_queue, category_based_dijkstra, category_based_dijkstra_with_queue};
+use pathfinding::{centi_seconds_to_time_format, format_number, map_paths, travel_path_to_csv};
+use pathfinding::{closest_dijkstra, closest_dijkstra_with_queue};
+use pathfinding::{full_dijkstra_with_queue, get_arc_flags, get_hub_labels, get_waypoints};
+use pathfinding::{Map, SearchResult};
+
+/// Parses a partition like "8x8" into (rows, columns).
+fn parse_cells(input: &str) -> (usize, usize) {
+    let (rows, columns) = input.split_once('x').expect("Cells must look like 8x8");
+    let rows: usize = rows.parse().expect("Could not parse rows");
+    let columns: usize = columns.parse().expect("Could not parse columns");
+    assert!(
+        rows > 0 && columns > 0 && rows * columns <= arc_flags::MAX_CELLS,
+        "Use between 1 and {} cells",
+        arc_flags::MAX_CELLS
+    );
+    (rows, columns)
 }
 
-struct Waypoint {
-    source: u32,
-    distances_to: Vec<u32>,
-    distances_from: Vec<u32>,
-}
+fn compare_alt_and_dijkstras(map: &Map, waypoints: &Waypoints, from: u32, to: u32) {
+    let from_name = map.get_name(from);
+    let to_name = map.get_name(to);
 
-impl Waypoint {
-    fn new(source: u32, distances_to: Vec<u32>, distances_from: Vec<u32>) -> Self {
-        Self {
-            source,
-            distances_to,
-            distances_from,
-        }
-    }
-}
+    println!("\nTesting Dijkstras: From {}, To {}", from_name, to_name);
+    let timer_dijkstras = Instant::now();
+    let result = closest_dijkstra(map, from as usize, to as usize).expect("No route was found");
+    let time_taken = timer_dijkstras.elapsed().as_millis();
+    println!(
+        "Djikstras took {} seconds, and visited {} nodes. Estimated travel time is: {}",
+        time_taken as f64 / 1000.0,
+        format_number(result.visited.len() as isize),
+        centi_seconds_to_time_format(result.drive_time as usize)
+    );
+    travel_path_to_csv(
+        map.path_coordinates(&result.path),
+        &format!("djikstra_path_{}_{}.csv", from_name, to_name),
+    )
+    .expect("Could not write result to file");
 
-#[derive(Clone)]
-struct Map {
-    nodes: Vec<Node>,
-    edges: Vec<Vec<EdgeTo>>,
-    points_of_interest: HashMap<u32, (u8, String)>,
+    println!("\nTesting ALT: From {}, To {}", from_name, to_name);
+    let timer_alt = Instant::now();
+    let result = alt(map, waypoints, from as usize, to as usize).expect("No route was found");
+    let time_taken = timer_alt.elapsed().as_millis();
+    println!(
+        "Alt took {} seconds, and visited {} nodes. Estimated travel time is: {}",
+        time_taken as f64 / 1000.0,
+        format_number(result.visited.len() as isize),
+        centi_seconds_to_time_format(result.drive_time as usize)
+    );
+    travel_path_to_csv(
+        map.path_coordinates(&result.path),
+        &format!("alt_path_{}_{}.csv", from_name, to_name),
+    )
+    .expect("Could not write result to file");
 }
 
-impl Map {
-    fn new() -> Self {
-        Self {
-            nodes: Vec::new(),
-            edges: Vec::new(),
-            points_of_interest: HashMap::new(),
-        }
-    }
-
-    fn from_nodes_edges_and_poi(
-        nodes: Vec<Node>,
-        edges: Vec<Vec<EdgeTo>>,
-        points_of_interest: HashMap<u32, (u8, String)>,
-    ) -> Self {
-        Self {
-            nodes,
-            edges,
-            points_of_interest,
-        }
-    }
+fn find_closest_information(map: &Map) {
+    const TRONDHEIM_LUFTHAVN: usize = 7172108;
+    const TRONDHEIM_TORG: usize = 4546048;
+    const HEMSEDAL: usize = 3509663;
+    const AMOUNT_OF_RESULTS: u32 = 8;
 
-    fn get_coordinates_from_node(&self, node: usize) -> (f64, f64) {
-        let n = &self.nodes[node];
-        (n.latitude, n.longitude)
-    }
+    const CHARGING_STATION: u8 = 4;
+    const PLACE_TO_EAT: u8 = 8;
+    const PLACE_TO_DRINK: u8 = 16;
 
-    fn get_reverse_copy(&self) -> Self {
-        let mut edges = vec![Vec::new(); self.edges.len()];
-        for i in 0..self.edges.len() {
-            for edge in &self.edges[i] {
-                edges[edge.to as usize].push(EdgeTo::new(
-                    i as u32,
-                    edge.drive_time,
-                    edge.length,
-                    edge.speed_limit,
-                ));
-            }
-        }
-        Map::from_nodes_edges_and_poi(self.nodes.clone(), edges, self.points_of_interest.clone())
+    println!(
+        "\nFinding {} closest charging stations near Trondheim lufthavn:",
+        AMOUNT_OF_RESULTS
+    );
+    let results =
+        category_based_dijkstra(map, TRONDHEIM_LUFTHAVN, CHARGING_STATION, AMOUNT_OF_RESULTS);
+    for result in &results {
+        println!(
+            "{} - {:?}",
+            map.points_of_interest[result].1,
+            map.get_coordinates_from_node(result.to_owned() as usize)
+        );
     }
+    travel_path_to_csv(
+        map.path_coordinates(&results),
+        &format!("{}_closest_charging_to_{}.csv", AMOUNT_OF_RESULTS, map.get_name(TRONDHEIM_LUFTHAVN as u32)),
+    )
+    .expect("Could not write result to file");
 
-    fn get_name(&self, node_id: u32) -> String{
-        self.points_of_interest
-        .get(&node_id)
-        .get_or_insert(&(0_u8, "Custom Waypoint".to_owned()))
-        .1.to_owned()
+    println!(
+        "\nFinding {} closest places to drink near Trondheim torg:",
+        AMOUNT_OF_RESULTS
+    );
+    let results = category_based_dijkstra(map, TRONDHEIM_TORG, PLACE_TO_DRINK, AMOUNT_OF_RESULTS);
+    for result in &results {
+        println!(
+            "{} - {:?}",
+            map.points_of_interest[result].1,
+            map.get_coordinates_from_node(result.to_owned() as usize)
+        );
     }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct Priority<'a, T> {
-    number: usize,
-    edges: &'a Vec<EdgeTo>,
-    cost: T,
-}
+    travel_path_to_csv(
+        map.path_coordinates(&results),
+        &format!("{}_closest_drinking_to_{}.csv", AMOUNT_OF_RESULTS, map.get_name(TRONDHEIM_TORG as u32)),
+    )
+    .expect("Could not write result to file");
 
-impl<'a, T> Priority<'a, T> {
-    fn new(number: usize, cost: T, edges: &'a Vec<EdgeTo>) -> Self {
-        Self {
-            number,
-            edges,
-            cost,
-        }
-    }
-}
 
-impl<T: Ord> Ord for Priority<'_, T> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.cost.cmp(&other.cost).reverse()
+    println!(
+        "\nFinding {} closest places to eat in Hemsedal:",
+        AMOUNT_OF_RESULTS
+    );
+    let results = category_based_dijkstra(map, HEMSEDAL, PLACE_TO_EAT, AMOUNT_OF_RESULTS);
+    for result in &results {
+        println!(
+            "{} - {:?}",
+            map.points_of_interest[result].1,
+            map.get_coordinates_from_node(result.to_owned() as usize)
+        );
     }
-}
+    travel_path_to_csv(
+        map.path_coordinates(&results),
+        &format!("{}_closest_eating_to_{}.csv", AMOUNT_OF_RESULTS, map.get_name(HEMSEDAL as u32)),
+    )
+    .expect("Could not write result to file");
 
-impl<T: Ord> PartialOrd for Priority<'_, T> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
 }
 
-fn get_waypoint_cost(source: usize, goal: usize, waypoints: &Vec<Waypoint>) -> u32 {
-    let mut diffs = Vec::new();
-    for waypoint in waypoints {
-        let diff1: u32 = {
-            if waypoint.distances_to[source] > waypoint.distances_to[goal] {
-                0
-            } else {
-                waypoint.distances_to[goal] - waypoint.distances_to[source]
+/// Usage: avoid FROM TO [--block-edge FROM,TO] [--block-node ID] [--avoid LAT,LON;LAT,LON;...]
+/// [--penalty FROM,TO,FACTOR] [--depart TIME | --arrive TIME] [--language en|nb]. `--avoid` also
+/// takes a box as LAT1,LON1,LAT2,LON2.
+fn route_with_overlay(map: &Map, waypoints: &Waypoints, args: &[String]) {
+    let mut args = args.to_vec();
+    let (schedule, language) = take_schedule(&mut args);
+    let mut overlay = Overlay::new();
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().expect("Missing value for option").as_str();
+        match arg.as_str() {
+            "--block-edge" => {
+                let l: Vec<u32> = value()
+                    .split(',')
+                    .map(|n| n.parse().expect("Could not parse edge"))
+                    .collect();
+                overlay.block_edge(l[0], l[1]);
             }
-        };
-        diffs.push(diff1);
-
-        let diff2: u32 = {
-            if waypoint.distances_from[goal] > waypoint.distances_from[source] {
-                0
-            } else {
-                waypoint.distances_from[source] - waypoint.distances_from[goal]
+            "--block-node" => {
+                overlay.block_node(value().parse().expect("Could not parse node"));
             }
-        };
-        diffs.push(diff2);
-    }
-    diffs.into_iter().max().expect("No waypoints found")
-}
-
-fn alt(
-    map: &Map,
-    waypoints: &Vec<Waypoint>,
-    source: usize,
-    goal: usize,
-) -> (usize, Vec<u32>, Vec<u32>) {
-    // Init variables
-    let length = map.edges.len();
-    let mut shortest_distances = vec![usize::MAX / 2; length];
-    let mut previous: Vec<Option<usize>> = vec![None; length];
-    let mut priority_queue: BinaryHeap<Priority<usize>> = BinaryHeap::new();
-    shortest_distances[source] = 0;
-
-    // Push source variable
-    priority_queue.push(Priority::new(source, 0, &map.edges[source]));
-    let mut visited = Vec::new();
-    while let Some(priority) = priority_queue.pop() {
-        visited.push(priority.number as u32);
-        if priority.number == goal {
-            break;
-        }
-        for neighbour in priority.edges {
-            let alt = shortest_distances[priority.number] + neighbour.drive_time as usize;
-            if alt < shortest_distances[neighbour.to as usize] {
-                shortest_distances[neighbour.to as usize] = alt;
-                previous[neighbour.to as usize] = Some(priority.number);
-                let cost = get_waypoint_cost(neighbour.to as usize, goal, waypoints) as usize + alt;
-                priority_queue.push(Priority::new(
-                    neighbour.to as usize,
-                    cost,
-                    &map.edges[neighbour.to as usize],
-                ));
+            "--avoid" => {
+                overlay.avoid_zone(Polygon::parse(value()).expect("Could not parse zone"));
             }
-            // println!("\n\n###################\n{:?}", priority_queue.iter()
-            // .map(|l|  (map.get_coordinates_from_node(l.number), centi_seconds_to_time_format(l.cost)))
-            // .collect::<Vec<((f64, f64), String)>>());
-        }
-    }
-
-    let mut path = Vec::new();
-    let mut prev = goal;
-    loop {
-        path.push(prev as u32);
-        if let Some(p) = previous[prev] {
-            prev = p;
-        } else {
-            panic!("Something went wrong with finding path");
-        }
-        if prev == source {
-            break;
-        }
-    }
-    path.reverse();
-
-    (shortest_distances[goal], path, visited)
-}
-
-fn closest_dijkstra(map: &Map, from: usize, to: usize) -> (usize, Vec<u32>, Vec<u32>) {
-    // Init variables
-    let length = map.edges.len();
-    let mut shortest_distances = vec![usize::MAX / 2; length];
-    let mut previous: Vec<Option<usize>> = vec![None; length];
-    let mut priority_queue: BinaryHeap<Priority<usize>> = BinaryHeap::new();
-    shortest_distances[from] = 0;
-
-    // Push source variable
-    priority_queue.push(Priority::new(from, 0, &map.edges[from]));
-    let mut visited = Vec::new();
-    while let Some(priority) = priority_queue.pop() {
-        visited.push(priority.number as u32);
-        if priority.number == to {
-            break;
-        }
-        for neighbour in priority.edges {
-            let alt = shortest_distances[priority.number] + neighbour.drive_time as usize;
-            if alt < shortest_distances[neighbour.to as usize] {
-                shortest_distances[neighbour.to as usize] = alt;
-                previous[neighbour.to as usize] = Some(priority.number);
-                priority_queue.push(Priority::new(
-                    neighbour.to as usize,
-                    alt,
-                    &map.edges[neighbour.to as usize],
-                ));
+            "--penalty" => {
+                let l: Vec<&str> = value().split(',').collect();
+                overlay.penalize_edge(
+                    l[0].parse().expect("Could not parse edge"),
+                    l[1].parse().expect("Could not parse edge"),
+                    l[2].parse().expect("Could not parse factor"),
+                );
             }
+            _ => positional.push(arg.parse::<usize>().expect("Could not parse node")),
         }
     }
-
-    let mut path = Vec::new();
-    let mut prev = to;
-    loop {
-        path.push(prev as u32);
-        if let Some(p) = previous[prev] {
-            prev = p;
-        } else {
-            panic!("Something went wrong with finding path");
-        }
-        if prev == from {
-            break;
-        }
+    let (from, to) = (positional[0], positional[1]);
+    let applied = overlay.apply(map);
+    if applied.is_node_blocked(to) {
+        println!("Warning: {} lies inside a blocked area", map.get_name(to as u32));
     }
-    path.reverse();
-
-    (shortest_distances[to], path, visited)
-}
 
-fn category_based_dijkstra(map: &Map, source: usize, category: u8, amount: u32) -> Vec<u32> {
-    // Init variables
-    let length = map.edges.len();
-    let mut shortest_distances = vec![usize::MAX / 2; length];
-    let mut previous: Vec<Option<usize>> = vec![None; length];
-    let mut priority_queue: BinaryHeap<Priority<usize>> = BinaryHeap::new();
-    shortest_distances[source] = 0;
-    let mut results = Vec::with_capacity(amount as usize);
-
-    // Push source variable
-    priority_queue.push(Priority::new(source, 0, &map.edges[source]));
-
-    while let Some(priority) = priority_queue.pop() {
-        if let Some(poi) = map.points_of_interest.get(&(priority.number as u32)) {
-            if poi.0 & category == category && !results.contains(&(priority.number as u32)) {
-                results.push(priority.number as u32)
-            }
-        }
-        if results.len() >= amount as usize {
-            break;
-        }
-        for neighbour in priority.edges {
-            let alt = shortest_distances[priority.number] + neighbour.drive_time as usize;
-            if alt < shortest_distances[neighbour.to as usize] {
-                shortest_distances[neighbour.to as usize] = alt;
-                previous[neighbour.to as usize] = Some(priority.number);
-                priority_queue.push(Priority::new(
-                    neighbour.to as usize,
-                    alt,
-                    &map.edges[neighbour.to as usize],
-                ));
+    let mut results = vec![("djikstra", closest_dijkstra_with_overlay(map, &applied, from, to))];
+    if overlay.keeps_alt_admissible() {
+        results.push(("alt", alt_with_overlay(map, waypoints, &applied, from, to)));
+    }
+    for (name, result) in results {
+        match result {
+            Some(result) => {
+                println!(
+                    "{} visited {} nodes.",
+                    name,
+                    format_number(result.visited.len() as isize)
+                );
+                print_schedule(&schedule, language, result.drive_time as usize);
+                travel_path_to_csv(
+                    map.path_coordinates(&result.path),
+                    &format!("{}_avoid_path_{}_{}.csv", name, from, to),
+                )
+                .expect("Could not write result to file");
             }
+            None => println!("{} found no route around the closures", name),
         }
     }
-    results
 }
 
-/// Returns: (shotests distances, previous)
-fn full_dijkstra(map: &Map, source: u32) -> (Vec<u32>, Vec<Option<usize>>) {
-    // Init variables
-    let length = map.edges.len();
-    let mut shortest_distances = vec![u32::MAX / 2; length];
-    let mut previous: Vec<Option<usize>> = vec![None; length];
-    let mut priority_queue: BinaryHeap<Priority<u32>> = BinaryHeap::new();
-    shortest_distances[source as usize] = 0;
-
-    // Push source variable
-    priority_queue.push(Priority::new(
-        source as usize,
-        0,
-        &map.edges[source as usize],
-    ));
-
-    while let Some(priority) = priority_queue.pop() {
-        for neighbour in priority.edges {
-            let alt = shortest_distances[priority.number] + neighbour.drive_time;
-            if alt < shortest_distances[neighbour.to as usize] {
-                shortest_distances[neighbour.to as usize] = alt;
-                previous[neighbour.to as usize] = Some(priority.number);
-                priority_queue.push(Priority::new(
-                    neighbour.to as usize,
-                    alt,
-                    &map.edges[neighbour.to as usize],
-                ));
-            }
+/// Usage: ev FROM TO RANGE_KM [--start KM] [--charge-minutes MIN] [--minutes-per-km MIN]
+///        [--depart TIME | --arrive TIME] [--language en|nb]
+fn route_electric_vehicle(map: &Map, args: &[String]) {
+    let mut args = args.to_vec();
+    let (schedule, language) = take_schedule(&mut args);
+    let mut positional = Vec::new();
+    let mut start_km = None;
+    let mut charge_minutes = 5.0;
+    let mut minutes_per_km = 0.3;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || -> f64 {
+            args.next()
+                .expect("Missing value for option")
+                .parse()
+                .expect("Could not parse number")
+        };
+        match arg.as_str() {
+            "--start" => start_km = Some(value()),
+            "--charge-minutes" => charge_minutes = value(),
+            "--minutes-per-km" => minutes_per_km = value(),
+            _ => positional.push(arg.to_owned()),
         }
     }
-    (shortest_distances, previous)
-}
+    let from: usize = positional[0].parse().expect("Could not parse node");
+    let to: usize = positional[1].parse().expect("Could not parse node");
+    let range_km: f64 = positional[2].parse().expect("Could not parse range");
+    let start_km = start_km.unwrap_or(range_km);
+    let charging = ChargingModel::new(
+        (charge_minutes * 6000.0) as u32,
+        (minutes_per_km * 6000.0) as u32,
+    );
 
-fn node_from_string(l: Vec<&str>) -> Node {
-    Node::new(
-        l[0].parse().expect("Could not parse to node"),
-        l[1].parse().expect("Could not parse to node"),
-        l[2].parse().expect("Could not parse to node"),
+    println!(
+        "\nRouting electric vehicle from {} to {} with {} km range",
+        map.get_name(from as u32),
+        map.get_name(to as u32),
+        range_km
+    );
+    let timer = Instant::now();
+    let route = ev_route(
+        map,
+        from,
+        to,
+        (range_km * 1000.0) as u32,
+        (start_km * 1000.0) as u32,
+        &charging,
+    );
+    let time_taken = timer.elapsed().as_millis();
+    let Some(route) = route else {
+        println!("No route within range was found");
+        return;
+    };
+    println!("Search took {} seconds.", time_taken as f64 / 1000.0);
+    print_schedule(&schedule, language, route.travel_time);
+    println!(
+        "Charging {} times, in total {}",
+        route.chargers.len(),
+        centi_seconds_to_time_format(route.charging_time)
+    );
+    for charger in &route.chargers {
+        println!(
+            "{} - {:?}",
+            map.get_name(*charger),
+            map.get_coordinates_from_node(*charger as usize)
+        );
+    }
+    println!("Arriving with {:.1} km left", route.arrival_charge as f64 / 1000.0);
+    travel_path_to_csv(
+        map.path_coordinates(&route.path),
+        &format!("ev_path_{}_{}.csv", from, to),
     )
+    .expect("Could not write result to file");
 }
 
-fn edge_from_string(l: Vec<&str>) -> EdgeTo {
-    EdgeTo::new(
-        l[1].parse().expect("Could not parse to node"),
-        l[2].parse().expect("Could not parse to node"),
-        l[3].parse().expect("Could not parse to node"),
-        l[4].parse().expect("Could not parse to node"),
-    )
-}
+fn report_components(map: &Map) {
+    println!("\nFinding strongly connected components ...");
+    let timer = Instant::now();
+    let (_, sizes) = strongly_connected_components(map);
+    println!(
+        "Found {} components in {} seconds",
+        format_number(sizes.len() as isize),
+        timer.elapsed().as_millis() as f64 / 1000.0
+    );
 
-fn get_map_from_paths(node_path: &str, edge_path: &str, poi_path: &str) -> io::Result<Map> {
-    let node_file = File::open(node_path)?;
-    let edge_file = File::open(edge_path)?;
-    let poi_file = File::open(poi_path)?;
-
-    let mut node_reader = BufReader::new(node_file);
-    let mut node_first_line = String::new();
-    node_reader.read_line(&mut node_first_line)?;
-    let node_lines: usize = node_first_line
-        .trim()
-        .parse()
-        .expect("Could not parse First line");
-    let nodes: Vec<Node> = node_reader
-        .lines()
-        .into_iter()
-        .map(|line| {
-            node_from_string(
-                line.expect("Failed to read line")
-                    .split_whitespace()
-                    .collect(),
-            )
-        })
-        .collect();
-
-    assert_eq!(nodes.len(), node_lines);
-
-    let mut edge_reader = BufReader::new(edge_file);
-    let mut edge_first_line = String::new();
-    edge_reader.read_line(&mut edge_first_line)?;
-    let edges_len: usize = edge_first_line
-        .trim()
-        .parse()
-        .expect("Could not parse First line");
-    let mut edges = vec![Vec::new(); edges_len];
-
-    for line in edge_reader.lines() {
-        let line = line.expect("Could not parse line");
-        let l: Vec<&str> = line.split_whitespace().collect();
-        edges[l[0].parse::<usize>().unwrap()].push(edge_from_string(l));
-    }
-
-    assert_eq!(nodes.len(), node_lines);
-
-    let mut poi_reader = BufReader::new(poi_file);
-    let mut poi_first_line = String::new();
-    poi_reader.read_line(&mut poi_first_line)?;
-    let poi_len: usize = poi_first_line
-        .trim()
-        .parse()
-        .expect("Could not parse First line");
-
-    let mut poi: HashMap<u32, (u8, String)> = HashMap::with_capacity(poi_len);
-    for line in poi_reader.lines() {
-        let line = line.expect("Could not parse line");
-        let l: Vec<&str> = line.split("\t").collect();
-        poi.insert(
-            l[0].parse().expect("Could not parse line"),
-            (
-                l[1].parse().expect("Could not parse line"),
-                l[2][1..(l[2].len() - 1)].to_string(),
-            ),
+    let mut sorted = sizes.clone();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    println!(
+        "Largest components: {}",
+        sorted
+            .iter()
+            .take(10)
+            .map(|s| format_number(*s as isize))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    for (low, high) in [(1, 1), (2, 9), (10, 99), (100, 9999), (10000, usize::MAX)] {
+        let in_range: Vec<&usize> = sizes.iter().filter(|s| (low..=high).contains(*s)).collect();
+        let label = match high {
+            _ if high == low => low.to_string(),
+            usize::MAX => format!("{}+", low),
+            _ => format!("{}-{}", low, high),
+        };
+        println!(
+            "Size {}: {} components with {} nodes",
+            label,
+            format_number(in_range.len() as isize),
+            format_number(in_range.into_iter().sum::<usize>() as isize)
         );
     }
-    Ok(Map::from_nodes_edges_and_poi(nodes, edges, poi))
-}
 
-fn travel_path_to_csv(travel_path: Vec<(f64, f64)>, file_path: &str) -> io::Result<()> {
-    let mut file = File::create(file_path)?;
-    file.write("Latitude,Longitude\n".as_bytes())?;
-    for p in travel_path {
-        file.write(format!("{},{}\n", p.0, p.1).as_bytes())?;
-    }
-    Ok(())
-}
-
-fn centi_seconds_to_time_format(centi_seconds: usize) -> String {
-    let seconds = centi_seconds / 100;
-
-    let mut str = format!(
-        "{} second{}.",
-        seconds % 60,
-        if seconds != 1 { "s" } else { "" }
+    let (pruned, original_ids) = map.get_largest_component_copy();
+    println!(
+        "Pruning to the largest component keeps {} of {} nodes",
+        format_number(pruned.nodes.len() as isize),
+        format_number(map.nodes.len() as isize)
     );
-
-    if seconds < 60 {
-        return str;
+    let path = "largest_component_ids.txt";
+    let mut writer = BufWriter::new(File::create(path).expect("File could not be created"));
+    writeln!(writer, "{}", original_ids.len()).expect("Could not write id mapping");
+    for (new_id, original_id) in original_ids.iter().enumerate() {
+        writeln!(writer, "{} {}", new_id, original_id).expect("Could not write id mapping");
     }
+    println!("Id mapping written to \"{}\"", path);
+}
 
-    let minutes = seconds / 60;
-    str = format!(
-        "{} minute{} and ",
-        minutes % 60,
-        if minutes != 1 { "s" } else { "" }
-    )
-        + &str;
-    if minutes < 60 {
-        return str;
-    }
+/// Usage: extract PREFIX (LAT1,LON1,LAT2,LON2 | LAT,LON;LAT,LON;...) [--largest-component]
+fn extract_region(map: &Map, args: &[String]) {
+    let prefix = &args[0];
+    let region = Polygon::parse(&args[1]).expect("Could not parse region");
 
-    let hours = minutes / 60;
-    str = format!("{} hour{}, ", hours % 24, if hours != 1 { "s" } else { "" }) + &str;
-    if hours < 24 {
-        return str;
+    println!("\nCutting out region ...");
+    let (mut region_map, _) = map.get_region_copy(&region);
+    if args.iter().any(|a| a == "--largest-component") {
+        region_map = region_map.get_largest_component_copy().0;
     }
+    println!(
+        "Region has {} nodes, {} edges and {} points of interest",
+        format_number(region_map.nodes.len() as isize),
+        format_number(region_map.edges.iter().map(Vec::len).sum::<usize>() as isize),
+        format_number(region_map.points_of_interest.len() as isize)
+    );
 
-    let days = hours / 24;
-    str = format!("{} day{}, ", days, if days != 1 { "s" } else { "" }) + &str;
-
-    str
+    let (node_path, edge_path, poi_path) = map_paths(prefix);
+    write_map_to_paths(&region_map, &node_path, &edge_path, &poi_path)
+        .expect("Could not write region to file");
+    println!("Region written to \"{}\", \"{}\" and \"{}\"", node_path, edge_path, poi_path);
 }
 
-fn get_file_as_bytes(path: &str) -> io::Result<Vec<u8>> {
-    let mut buffer = Vec::new();
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-    reader.read_to_end(&mut buffer)?;
-    Ok(buffer)
+/// Usage: validate [JSON_PATH]
+fn validate_map(map: &Map, args: &[String]) {
+    println!("\nValidating map ...");
+    let report = MapReport::new(map);
+    print!("{}", report.to_text());
+
+    let path = args.first().map_or("map_report.json", String::as_str);
+    let mut file = File::create(path).expect("File could not be created");
+    file.write_all(report.to_json().as_bytes())
+        .expect("Could not write report to file");
+    println!("Report written to \"{}\"", path);
 }
 
-fn write_file_as_bytes(path: &str, bytes: &[u8]) -> io::Result<usize> {
-    let file = File::create(path).expect("File could not be created");
-    let mut writer = BufWriter::new(file);
-    writer.write(bytes)
+/// Reads node ids given as "1,2,3" or as a file with whitespace separated ids.
+fn parse_node_list(input: &str) -> Vec<u32> {
+    let text = if input.split(',').all(|n| n.trim().parse::<u32>().is_ok()) {
+        input.replace(',', " ")
+    } else {
+        std::fs::read_to_string(input).expect("Could not read node list")
+    };
+    text.split_whitespace()
+        .map(|n| n.parse().expect("Could not parse node"))
+        .collect()
 }
 
-fn get_u32_from_byte_array(bytes: &[u8]) -> u32 {
-    assert_eq!(bytes.len(), 4);
-    let mut num = 0;
-
-    num |= bytes[3] as u32;
-    num |= (bytes[2] as u32) << 8;
-    num |= (bytes[1] as u32) << 16;
-    num |= (bytes[0] as u32) << 24;
+/// Usage: matrix SOURCES TARGETS [OUTPUT_PREFIX] [--threads N]
+fn create_travel_time_matrix(map: &Map, args: &[String]) {
+    let mut args = args.to_vec();
+    let threads = take_option(&mut args, "--threads").map_or_else(
+        || thread::available_parallelism().map_or(4, |n| n.get()),
+        |n| n.parse().expect("Could not parse thread count"),
+    );
+    let sources = parse_node_list(&args[0]);
+    let targets = parse_node_list(&args[1]);
+    let prefix = args.get(2).map_or("matrix", String::as_str);
 
-    num
-}
+    println!(
+        "\nCalculating {} x {} travel time matrix on {} threads ...",
+        sources.len(),
+        targets.len(),
+        threads
+    );
+    let timer = Instant::now();
+    let matrix = travel_time_matrix(map, &sources, &targets, threads);
+    println!(
+        "Matrix took {} seconds, {} pairs are unreachable",
+        timer.elapsed().as_millis() as f64 / 1000.0,
+        format_number(matrix.times.iter().filter(|t| **t == matrix::UNREACHABLE).count() as isize)
+    );
 
-fn get_byte_array_from_u32(input: u32) -> [u8; 4] {
-    let b1: u8 = ((input >> 24) & 0xff) as u8;
-    let b2: u8 = ((input >> 16) & 0xff) as u8;
-    let b3: u8 = ((input >> 8) & 0xff) as u8;
-    let b4: u8 = (input & 0xff) as u8;
-    [b1, b2, b3, b4]
+    let csv_path = format!("{}.csv", prefix);
+    let binary_path = format!("{}.bin", prefix);
+    matrix.write_csv(&csv_path).expect("Could not write result to file");
+    matrix.write_binary(&binary_path).expect("Could not write result to file");
+    println!("Matrix written to \"{}\" and \"{}\"", csv_path, binary_path);
 }
 
-fn create_waypoints(map: &Map, sources: &Vec<u32>) {
-    let reverse_map = &map.get_reverse_copy();
-    thread::scope(|scope| {
-        let mut handels = Vec::new();
-        for source in sources {
-            handels.push(scope.spawn(move || {
-                let mut bytes: Vec<u8> = Vec::new();
-                println!("Calculating for source: {}", source);
-                bytes.extend(get_byte_array_from_u32(source.to_owned()));
-        
-                // To
-                let (dijk_distances_to, _) = full_dijkstra(map, source.to_owned());
-                bytes.extend(get_byte_array_from_u32(dijk_distances_to.len() as u32));
-        
-                for dist in dijk_distances_to {
-                    bytes.extend(get_byte_array_from_u32(dist as u32))
-                }
-        
-                // From
-                let (dijk_distances_from, _) = full_dijkstra(reverse_map, source.to_owned());
-                bytes.extend(get_byte_array_from_u32(dijk_distances_from.len() as u32));
-                
-                for dist in dijk_distances_from {
-                    bytes.extend(get_byte_array_from_u32(dist as u32))
-                }
-                println!("Done calcuating: {}", source);
-                bytes
-            }));
-        }
-
-        let mut bytes = Vec::new();
-        for handle in handels {
-            bytes.extend(handle.join().expect("Couldn't join on the associated thread"));
-        }
-
-        println!("Writing to file...");
-        if write_file_as_bytes("waypoints.bin", &bytes).is_ok() {
-            println!("Waypoints succsessfully written to file \"waypoints.bin\"");
-        } else {
-            println!("Waypoints could not be written to file \"waypoints.bin\"");
-        }
+/// Usage: meet NODES [--objective max|sum] [--category N]
+fn find_meeting_point(map: &Map, args: &[String]) {
+    let mut args = args.to_vec();
+    let objective = take_option(&mut args, "--objective").map_or(Objective::Latest, |name| {
+        Objective::from_name(&name).expect("Unknown objective, use max or sum")
     });
+    let category = take_option(&mut args, "--category")
+        .map(|c| c.parse::<u8>().expect("Could not parse category"));
+    let travellers = parse_node_list(&args[0]);
+
+    println!("\nFinding where {} travellers should meet ...", travellers.len());
+    let timer = Instant::now();
+    let meeting = meeting_point(map, &travellers, objective, category)
+        .expect("No place every traveller can reach was found");
+    println!("Search took {} seconds.", timer.elapsed().as_millis() as f64 / 1000.0);
+    println!(
+        "Meet at {} - {:?}",
+        map.get_name(meeting.node),
+        map.get_coordinates_from_node(meeting.node as usize)
+    );
+    let travels = travellers.iter().zip(&meeting.times).zip(&meeting.paths);
+    for ((traveller, time), path) in travels {
+        println!(
+            "From {}: {}",
+            map.get_name(*traveller),
+            centi_seconds_to_time_format(*time as usize)
+        );
+        travel_path_to_csv(
+            map.path_coordinates(path),
+            &format!("meet_{}_{}.csv", meeting.node, traveller),
+        )
+        .expect("Could not write result to file");
+    }
 }
 
-fn get_waypoints_from_bytes(bytes: &Vec<u8>) -> Vec<Waypoint> {
-    let mut pointer: usize = 0;
-    let mut res: Vec<Waypoint> = Vec::new();
-    loop {
-        if pointer >= bytes.len() {
-            break;
-        }
-        let mut distances_to = Vec::new();
-        let mut distances_from = Vec::new();
+/// Usage: directions FROM TO [--depart TIME | --arrive TIME] [--language en|nb]
+fn print_directions(map: &Map, args: &[String]) {
+    let mut args = args.to_vec();
+    let (schedule, language) = take_schedule(&mut args);
+    let from: u32 = args[0].parse().expect("Could not parse from node");
+    let to: u32 = args[1].parse().expect("Could not parse to node");
+    assert_ne!(from, to, "From and to must be different nodes");
 
-        let source = get_u32_from_byte_array(&bytes[pointer..pointer + 4]);
-        pointer += 4;
-        let length_to = get_u32_from_byte_array(&bytes[pointer..pointer + 4]);
-        pointer += 4;
-
-        for _ in 0..length_to {
-            distances_to.push(get_u32_from_byte_array(&bytes[pointer..pointer + 4]));
-            pointer += 4
-        }
-        let length_from = get_u32_from_byte_array(&bytes[pointer..pointer + 4]);
-
-        pointer += 4;
-        for _ in 0..length_from {
-            distances_from.push(get_u32_from_byte_array(&bytes[pointer..pointer + 4]));
-            pointer += 4
-        }
-
-        res.push(Waypoint::new(source, distances_to, distances_from));
+    let result = closest_dijkstra(map, from as usize, to as usize).expect("No route was found");
+    let route = Route::from_node_path(map, &result.path).expect("Path does not follow the map");
+    println!(
+        "\nDirections from {} to {}: {:.1} km",
+        map.get_name(from),
+        map.get_name(to),
+        route.length() as f64 / 1000.0
+    );
+    print_schedule(&schedule, language, route.drive_time() as usize);
+    for (i, instruction) in turn_by_turn(map, &route).iter().enumerate() {
+        println!("{:>3}. {}", i + 1, instruction.to_text());
     }
-    res
+    travel_path_to_csv(
+        map.path_coordinates(&route.nodes()),
+        &format!("directions_{}_{}.csv", from, to),
+    )
+    .expect("Could not write result to file");
 }
 
-fn get_waypoints(map: &Map) -> Vec<Waypoint> {
-    // 5697698	8	"Bergen Pizza"
-    // 7283163	8	"Pizzabakeren Alta"
-    // 1906903	8	"Kungsan Pizzeria"
-    // 493001	8	"Pizzeria Roihu"
-
-    let path = "waypoints.bin";
-    let sources = vec![5697698, 7283163, 1906903, 493001];
-    if let Ok(bytes) = get_file_as_bytes(path) {
-        return get_waypoints_from_bytes(&bytes);
-    } else {
-        create_waypoints(map, &sources)
+/// Usage: detour FROM TO CATEGORY [--count K] [--max-minutes MIN]
+///
+/// Only stops that add at most `--max-minutes` to the drive time are listed.
+fn find_detours(map: &Map, args: &[String]) {
+    let mut args = args.to_vec();
+    let count = take_option(&mut args, "--count")
+        .map_or(5, |n| n.parse().expect("Could not parse count"));
+    let max_minutes: f64 = take_option(&mut args, "--max-minutes")
+        .map_or(30.0, |m| m.parse().expect("Could not parse minutes"));
+    let from: u32 = args[0].parse().expect("Could not parse from node");
+    let to: u32 = args[1].parse().expect("Could not parse to node");
+    let category: u8 = args[2].parse().expect("Could not parse category");
+
+    let result = closest_dijkstra(map, from as usize, to as usize).expect("No route was found");
+    println!(
+        "\nStops of category {} between {} and {}",
+        category,
+        map.get_name(from),
+        map.get_name(to)
+    );
+    println!(
+        "Without a stop: {}",
+        centi_seconds_to_time_format(result.drive_time as usize)
+    );
+    let timer = Instant::now();
+    let reverse = Reversed::new(map);
+    let detours = detours(
+        map,
+        &reverse,
+        &result.path,
+        category,
+        (max_minutes * 6000.0) as u32,
+        count,
+    )
+    .expect("Path does not follow the map");
+    println!("Search took {} seconds.", timer.elapsed().as_millis() as f64 / 1000.0);
+    if detours.is_empty() {
+        println!("No stop adding at most {} minutes was found", max_minutes);
+    }
+    for detour in &detours {
+        println!(
+            "{} - {:?} adds {}",
+            map.get_name(detour.point_of_interest),
+            map.get_coordinates_from_node(detour.point_of_interest as usize),
+            centi_seconds_to_time_format(detour.added_time as usize)
+        );
+        travel_path_to_csv(
+            map.path_coordinates(&detour.path),
+            &format!("detour_{}_{}_{}.csv", from, to, detour.point_of_interest),
+        )
+        .expect("Could not write result to file");
     }
+}
 
-    if let Ok(bytes) = get_file_as_bytes(path) {
-        get_waypoints_from_bytes(&bytes)
-    } else {
-        panic!("File could not be read after creation");
+/// Usage: match TRACE_CSV [OUTPUT_PREFIX] [--radius METERS]
+fn match_gps_trace(map: &Map, args: &[String]) {
+    let mut args = args.to_vec();
+    let mut parameters = MatchingParameters::default();
+    if let Some(radius) = take_option(&mut args, "--radius") {
+        parameters.search_radius = radius.parse().expect("Could not parse radius");
     }
+    let trace = read_trace(&args[0]).expect("Could not read trace");
+    let prefix = args.get(1).map_or("matched", String::as_str);
+
+    println!("\nMatching {} GPS fixes ...", format_number(trace.len() as isize));
+    let timer = Instant::now();
+    let index = GridIndex::new(map, parameters.search_radius);
+    let result = match_trace(map, &index, &trace, parameters);
+    let matched: Vec<&MatchedPoint> = result.points.iter().filter(|p| p.node.is_some()).collect();
+    println!(
+        "Matched {} of {} fixes in {} seconds, average confidence {:.3}",
+        format_number(matched.len() as isize),
+        format_number(trace.len() as isize),
+        timer.elapsed().as_millis() as f64 / 1000.0,
+        matched.iter().map(|p| p.confidence).sum::<f64>() / matched.len().max(1) as f64
+    );
+
+    let points_path = format!("{}_points.csv", prefix);
+    let path_path = format!("{}_path.csv", prefix);
+    result.write_csv(&points_path).expect("Could not write result to file");
+    travel_path_to_csv(map.path_coordinates(&result.path), &path_path)
+    .expect("Could not write result to file");
+    println!("Results written to \"{}\" and \"{}\"", points_path, path_path);
 }
 
-fn compare_alt_and_dijkstras(map: &Map, waypoints: &Vec<Waypoint>, from: u32, to: u32) {
-    let from_name = map.get_name(from);
-    let to_name = map.get_name(to);
+/// Usage: import OSM_FILE PREFIX
+fn import_openstreetmap(args: &[String]) {
+    println!("\nImporting \"{}\" ...", args[0]);
+    let timer = Instant::now();
+    let (map, osm_ids) = import_osm(&args[0]).expect("Could not read OSM file");
+    println!(
+        "Imported {} nodes, {} edges and {} points of interest in {} seconds",
+        format_number(map.nodes.len() as isize),
+        format_number(map.edges.iter().map(Vec::len).sum::<usize>() as isize),
+        format_number(map.points_of_interest.len() as isize),
+        timer.elapsed().as_millis() as f64 / 1000.0
+    );
 
-    println!("\nTesting Dijkstras: From {}, To {}", from_name, to_name);
-    let timer_dijkstras = Instant::now();
-    let (time_distance, path, visited) = closest_dijkstra(map, from as usize, to as usize);
-    let time_taken = timer_dijkstras.elapsed().as_millis();
+    let (node_path, edge_path, poi_path) = map_paths(&args[1]);
+    write_map_to_paths(&map, &node_path, &edge_path, &poi_path)
+        .expect("Could not write map to file");
+    let id_path = format!("{}_osm_ids.txt", args[1]);
+    let mut writer = BufWriter::new(File::create(&id_path).expect("File could not be created"));
+    writeln!(writer, "{}", osm_ids.len()).expect("Could not write id mapping");
+    for (node, osm_id) in osm_ids.iter().enumerate() {
+        writeln!(writer, "{} {}", node, osm_id).expect("Could not write id mapping");
+    }
+    writer.flush().expect("Could not write id mapping");
     println!(
-        "Djikstras took {} seconds, and visited {} nodes. Estimated travel time is: {}",
-        time_taken as f64 / 1000.0,
-        format_number(visited.len() as isize),
-        centi_seconds_to_time_format(time_distance)
+        "Map written to \"{}\", \"{}\" and \"{}\", OSM ids to \"{}\"",
+        node_path, edge_path, poi_path, id_path
+    );
+}
+
+/// Usage: hubs FROM TO [--order degree|coverage] [--depart TIME | --arrive TIME]
+///        [--language en|nb]
+fn route_with_hub_labels(map: &Map, label_path: &str, args: &[String]) {
+    let mut args = args.to_vec();
+    let (schedule, language) = take_schedule(&mut args);
+    let order = take_option(&mut args, "--order").map_or(HubOrder::coverage(), |name| {
+        HubOrder::from_name(&name).expect("Unknown order, use degree or coverage")
+    });
+    let from: u32 = args[0].parse().expect("Could not parse from node");
+    let to: u32 = args[1].parse().expect("Could not parse to node");
+    let labels =
+        get_hub_labels(map, label_path, order).expect("Could not load or create hub labels");
+
+    println!("\nTesting hub labels: From {}, To {}", map.get_name(from), map.get_name(to));
+    let timer = Instant::now();
+    let Some(query) = labels.query(from as usize, to as usize) else {
+        println!("There is no route from {} to {}", from, to);
+        return;
+    };
+    let query_time = timer.elapsed().as_secs_f64() * 1_000_000.0;
+    let timer = Instant::now();
+    let path = labels
+        .path(from as usize, to as usize)
+        .expect("Labels with a distance have a path");
+    let path_time = timer.elapsed().as_secs_f64() * 1_000_000.0;
+    println!(
+        "Query took {:.1} µs comparing {} label entries, unpacking {} nodes {:.1} µs.",
+        query_time,
+        query.compared,
+        format_number(path.len() as isize),
+        path_time
     );
+    print_schedule(&schedule, language, query.drive_time as usize);
     travel_path_to_csv(
-        path.into_iter()
-            .map(|n| map.get_coordinates_from_node(n as usize))
-            .collect(),
-        &format!("djikstra_path_{}_{}.csv", from_name, to_name),
+        map.path_coordinates(&path),
+        &format!("hub_path_{}_{}.csv", from, to),
     )
     .expect("Could not write result to file");
+}
 
-    println!("\nTesting ALT: From {}, To {}", from_name, to_name);
-    let timer_alt = Instant::now();
-    let (time_distance, path, visited) = alt(map, waypoints, from as usize, to as usize);
-    let time_taken = timer_alt.elapsed().as_millis();
+/// Usage: arc-flags FROM TO [--cells ROWSxCOLUMNS]
+fn compare_alt_and_arc_flags(
+    map: &Map,
+    waypoints: &Waypoints,
+    flag_path: &str,
+    workers: usize,
+    args: &[String],
+) {
+    let mut args = args.to_vec();
+    let (rows, columns) = parse_cells(&take_option(&mut args, "--cells").unwrap_or("8x8".into()));
+    let from: usize = args[0].parse().expect("Could not parse from node");
+    let to: usize = args[1].parse().expect("Could not parse to node");
+    assert_ne!(from, to, "From and to must be different nodes");
+    let arc_flags = get_arc_flags(map, flag_path, rows, columns, workers)
+        .expect("Could not load or create arc flags");
+
+    println!("\nFrom {}, To {}", map.get_name(from as u32), map.get_name(to as u32));
+    let timer = Instant::now();
+    let Some(alt_result) = alt(map, waypoints, from, to) else {
+        println!("There is no route from {} to {}", from, to);
+        return;
+    };
+    let alt_time = timer.elapsed().as_secs_f64();
+    let timer = Instant::now();
+    let result = arc_flags_dijkstra(map, &arc_flags, from, to).expect("ALT found a route");
+    let arc_flag_time = timer.elapsed().as_secs_f64();
+    assert_eq!(result.drive_time, alt_result.drive_time, "Arc flags and ALT disagree");
     println!(
-        "Alt took {} seconds, and visited {} nodes. Estimated travel time is: {}",
-        time_taken as f64 / 1000.0,
-        format_number(visited.len() as isize),
-        centi_seconds_to_time_format(time_distance)
+        "Alt took {:.3} seconds and visited {} nodes, arc flags took {:.3} seconds and visited \
+         {} nodes, {:.1}x faster. Estimated travel time is: {}",
+        alt_time,
+        format_number(alt_result.visited.len() as isize),
+        arc_flag_time,
+        format_number(result.visited.len() as isize),
+        alt_time / arc_flag_time,
+        centi_seconds_to_time_format(result.drive_time as usize)
     );
     travel_path_to_csv(
-        path.into_iter()
-            .map(|n| map.get_coordinates_from_node(n as usize))
-            .collect(),
-        &format!("alt_path_{}_{}.csv", from_name, to_name),
+        map.path_coordinates(&result.path),
+        &format!("arc_flag_path_{}_{}.csv", from, to),
     )
     .expect("Could not write result to file");
 }
 
-fn format_number(number: isize) -> String {
-    let mut str = Vec::new();
-    let mut copy = Clone::clone(&number);
+/// Usage: render FROM TO [OUTPUT_PREFIX] [--projection mercator|equirectangular] [--width PX]
+///        [--box LAT1,LON1,LAT2,LON2]
+fn render_searches(map: &Map, waypoints: &Waypoints, args: &[String]) {
+    let mut args = args.to_vec();
+    let projection = take_option(&mut args, "--projection").map_or(Projection::WebMercator, |p| {
+        Projection::from_name(&p).expect("Unknown projection, use mercator or equirectangular")
+    });
+    let width: u32 = take_option(&mut args, "--width")
+        .map_or(1200, |w| w.parse().expect("Could not parse width"));
+    let bounds = take_option(&mut args, "--box").map(|b| {
+        BoundingBox::parse(&b).expect("The bounding box must look like LAT1,LON1,LAT2,LON2")
+    });
+    let from: usize = args[0].parse().expect("Could not parse from node");
+    let to: usize = args[1].parse().expect("Could not parse to node");
+    assert_ne!(from, to, "From and to must be different nodes");
+    let prefix = args.get(2).map_or("search", String::as_str);
+
+    let dijkstra = closest_dijkstra(map, from, to).expect("No route was found");
+    let alt = alt(map, waypoints, from, to).expect("No route was found");
+    // Both drawings show the area Dijkstra searched, so ALT's smaller search stands out
+    let bounds = bounds.unwrap_or_else(|| {
+        BoundingBox::around(map, &dijkstra.visited, 0.05).expect("The search visits the source")
+    });
 
-    while copy > 1000 {
-        str.push((copy % 1000).to_string());
-        copy /= 1000;
+    for (name, result) in [("dijkstra", dijkstra), ("alt", alt)] {
+        let svg_path = format!("{}_{}.svg", prefix, name);
+        SvgMap::new(map, bounds, projection, width)
+            .draw_road_network()
+            .draw_visited(&result.visited)
+            .draw_points_of_interest()
+            .draw_landmarks(waypoints.sources())
+            .draw_path(&result.path, "#111111", "Shortest path")
+            .write(&svg_path)
+            .expect("Could not write result to file");
+        println!(
+            "Drawing of {} visiting {} nodes written to \"{}\"",
+            name,
+            format_number(result.visited.len() as isize),
+            svg_path
+        );
     }
-    str.push((copy % 1000).to_string());
-
-    str.into_iter().rev().collect::<Vec<String>>().join(" ")
 }
 
-fn find_closest_information(map: &Map) {
-    const TRONDHEIM_LUFTHAVN: usize = 7172108;
-    const TRONDHEIM_TORG: usize = 4546048;
-    const HEMSEDAL: usize = 3509663;
-    const AMOUNT_OF_RESULTS: u32 = 8;
-
-    const CHARGING_STATION: u8 = 4;
-    const PLACE_TO_EAT: u8 = 8;
-    const PLACE_TO_DRINK: u8 = 16;
-
-    println!(
-        "\nFinding {} closest charging stations near Trondheim lufthavn:",
-        AMOUNT_OF_RESULTS
-    );
-    let results =
-        category_based_dijkstra(map, TRONDHEIM_LUFTHAVN, CHARGING_STATION, AMOUNT_OF_RESULTS);
-    for result in &results {
-        println!(
-            "{} - {:?}",
-            map.points_of_interest[&result].1,
-            map.get_coordinates_from_node(result.to_owned() as usize)
+/// Usage: raster OUTPUT_PREFIX (--category N | --nodes A,B,...) [--from] [--cell DEGREES]
+///        [--max-minutes M] [--box LAT1,LON1,LAT2,LON2]
+///
+/// Drive time to the closest of the given nodes or points of interest, or from them with
+/// `--from`, as PGM and PPM images with world files and as an ASCII grid.
+fn export_travel_time_raster(map: &Map, args: &[String]) {
+    let mut args = args.to_vec();
+    let mut sources: Vec<u32> = take_option(&mut args, "--nodes")
+        .map(|n| parse_node_list(&n))
+        .unwrap_or_default();
+    if let Some(category) = take_option(&mut args, "--category") {
+        let category: u8 = category.parse().expect("Could not parse category");
+        sources.extend(
+            map.points_of_interest
+                .iter()
+                .filter(|(_, (c, _))| c & category == category)
+                .map(|(node, _)| *node),
         );
     }
-    travel_path_to_csv(
-        results.into_iter()
-            .map(|n| map.get_coordinates_from_node(n as usize))
-            .collect(),
-        &format!("{}_closest_charging_to_{}.csv", AMOUNT_OF_RESULTS, map.get_name(TRONDHEIM_LUFTHAVN as u32)),
-    )
-    .expect("Could not write result to file");
+    let from_sources = take_flag(&mut args, "--from");
+    let cell_size: f64 = take_option(&mut args, "--cell")
+        .map_or(0.01, |c| c.parse().expect("Could not parse cell size"));
+    let max_minutes: Option<f64> = take_option(&mut args, "--max-minutes")
+        .map(|m| m.parse().expect("Could not parse minutes"));
+    let bounds = take_option(&mut args, "--box").map(|b| {
+        BoundingBox::parse(&b).expect("The bounding box must look like LAT1,LON1,LAT2,LON2")
+    });
+    let prefix = args.first().map_or("travel_time", String::as_str);
+    assert!(!sources.is_empty(), "Give the sources with --nodes or --category");
 
     println!(
-        "\nFinding {} closest places to drink near Trondheim torg:",
-        AMOUNT_OF_RESULTS
+        "\nDrive times {} the closest of {} sources ...",
+        if from_sources { "from" } else { "to" },
+        format_number(sources.len() as isize)
+    );
+    let timer = Instant::now();
+    let distances = if from_sources {
+        multi_source_dijkstra(map, &sources)
+    } else {
+        multi_source_dijkstra(&Reversed::new(map), &sources)
+    };
+    let raster = TravelTimeRaster::new(map, &distances, bounds, cell_size)
+        .expect("No node was reached");
+    let max_time = max_minutes.map_or(raster.max_time(), |m| (m * 6000.0) as u32);
+    println!(
+        "Rasterised to {} x {} cells in {} seconds, longest drive time {}",
+        raster.columns,
+        raster.rows,
+        timer.elapsed().as_millis() as f64 / 1000.0,
+        centi_seconds_to_time_format(raster.max_time() as usize)
     );
-    let results = category_based_dijkstra(map, TRONDHEIM_TORG, PLACE_TO_DRINK, AMOUNT_OF_RESULTS);
-    for result in &results {
-        println!(
-            "{} - {:?}",
-            map.points_of_interest[&result].1,
-            map.get_coordinates_from_node(result.to_owned() as usize)
-        );
-    }
-    travel_path_to_csv(
-        results.into_iter()
-            .map(|n| map.get_coordinates_from_node(n as usize))
-            .collect(),
-        &format!("{}_closest_drinking_to_{}.csv", AMOUNT_OF_RESULTS, map.get_name(TRONDHEIM_TORG as u32)),
-    )
-    .expect("Could not write result to file");
 
+    let paths = ["pgm", "pgw", "ppm", "ppw", "asc"].map(|e| format!("{}.{}", prefix, e));
+    raster.write_pgm(&paths[0], max_time).expect("Could not write result to file");
+    raster.write_world_file(&paths[1]).expect("Could not write result to file");
+    raster.write_ppm(&paths[2], max_time).expect("Could not write result to file");
+    raster.write_world_file(&paths[3]).expect("Could not write result to file");
+    raster.write_ascii_grid(&paths[4]).expect("Could not write result to file");
+    println!("Raster written to \"{}\"", paths.join("\", \""));
+}
 
+/// Usage: benchmark [OUTPUT_PREFIX] [--queries N] [--rank-sources N] [--seed N] [--hub-labels]
+///        [--arc-flags ROWSxCOLUMNS]
+fn benchmark_searches(
+    map: &Map,
+    waypoints: &Waypoints,
+    profile: &VehicleProfile,
+    prefix: &str,
+    workers: usize,
+    args: &[String],
+) {
+    let mut args = args.to_vec();
+    let parse = |value: Option<String>, default: u64| {
+        value.map_or(default, |v| v.parse().expect("Could not parse number"))
+    };
+    let query_count = parse(take_option(&mut args, "--queries"), 100) as usize;
+    let rank_sources = parse(take_option(&mut args, "--rank-sources"), 10) as usize;
+    let seed = parse(take_option(&mut args, "--seed"), 42);
+    let hub_labels = take_flag(&mut args, "--hub-labels").then(|| {
+        get_hub_labels(map, &profile.hub_label_path(prefix), HubOrder::coverage())
+            .expect("Could not load or create hub labels")
+    });
+    let arc_flags = take_option(&mut args, "--arc-flags").map(|cells| {
+        let (rows, columns) = parse_cells(&cells);
+        get_arc_flags(map, &profile.arc_flag_path(prefix), rows, columns, workers)
+            .expect("Could not load or create arc flags")
+    });
+    let prefix = args.first().map_or("benchmark", String::as_str);
+
+    println!("\nGenerating queries with seed {} ...", seed);
+    let nodes = largest_component_nodes(map);
+    assert!(!nodes.is_empty(), "The map has no nodes to query");
+    let mut random = Random::new(seed);
+    let mut queries = random_queries(&nodes, query_count, &mut random);
+    queries.extend(rank_queries(map, &nodes, rank_sources, &mut random));
+
+    let bound = StraightLine::new(map);
+    let reverse = Reversed::new(map);
+    let mut algorithms = vec![
+        Algorithm::new("Dijkstra", |from, to| {
+            let result = closest_dijkstra(map, from, to).expect("Queries stay in one component");
+            (result.drive_time, result.visited.len())
+        }),
+        Algorithm::new("A*", |from, to| {
+            let result = astar(map, &bound, from, to).expect("Queries stay in one component");
+            (result.drive_time, result.visited.len())
+        }),
+        Algorithm::new("Bidirectional", |from, to| {
+            let result = bidirectional_dijkstra(map, &reverse, from, to)
+                .expect("Queries stay in one component");
+            (result.drive_time, result.visited.len())
+        }),
+        Algorithm::new("ALT", |from, to| {
+            let result = alt(map, waypoints, from, to).expect("Queries stay in one component");
+            (result.drive_time, result.visited.len())
+        }),
+    ];
+    if let Some(labels) = &hub_labels {
+        algorithms.push(Algorithm::new("Hub labels", |from, to| {
+            let query = labels.query(from, to).expect("Queries stay in one component");
+            (query.drive_time, query.compared)
+        }));
+    }
+    if let Some(flags) = &arc_flags {
+        algorithms.push(Algorithm::new("Arc flags", |from, to| {
+            let result =
+                arc_flags_dijkstra(map, flags, from, to).expect("Queries stay in one component");
+            (result.drive_time, result.visited.len())
+        }));
+    }
     println!(
-        "\nFinding {} closest places to eat in Hemsedal:",
-        AMOUNT_OF_RESULTS
+        "Running {} queries with {} algorithms ...",
+        format_number(queries.len() as isize),
+        algorithms.len()
     );
-    let results = category_based_dijkstra(map, HEMSEDAL, PLACE_TO_EAT, AMOUNT_OF_RESULTS);
-    for result in &results {
+    let result = run_benchmark(queries, &algorithms);
+    print!("{}", result.to_table());
+
+    let csv_path = format!("{}_queries.csv", prefix);
+    let summary_path = format!("{}_summary.csv", prefix);
+    result.write_csv(&csv_path).expect("Could not write result to file");
+    result.write_summary_csv(&summary_path).expect("Could not write result to file");
+    println!("Results written to \"{}\" and \"{}\"", csv_path, summary_path);
+}
+
+/// Runs every search with both priority queues on the same queries.
+fn benchmark_queues(map: &Map, waypoints: &Waypoints, pairs: &[(u32, u32)]) {
+    fn report(name: &str, timer: Instant, visited: usize) {
         println!(
-            "{} - {:?}",
-            map.points_of_interest[&result].1,
-            map.get_coordinates_from_node(result.to_owned() as usize)
+            "{:<34}{:>10.3} s{:>14} visited",
+            name,
+            timer.elapsed().as_millis() as f64 / 1000.0,
+            format_number(visited as isize)
         );
     }
-    travel_path_to_csv(
-        results.into_iter()
-            .map(|n| map.get_coordinates_from_node(n as usize))
-            .collect(),
-        &format!("{}_closest_eating_to_{}.csv", AMOUNT_OF_RESULTS, map.get_name(HEMSEDAL as u32)),
-    )
-    .expect("Could not write result to file");
+    fn visited_count(result: &Option<SearchResult>) -> usize {
+        result.as_ref().map_or(0, |r| r.visited.len())
+    }
 
+    for &(from, to) in pairs {
+        println!("\nFrom {}, To {}", map.get_name(from), map.get_name(to));
+        let (from, to) = (from as usize, to as usize);
+
+        let timer = Instant::now();
+        let result = closest_dijkstra_with_queue::<LazyBinaryHeap<u32>>(map, from, to);
+        report("Djikstra, binary heap", timer, visited_count(&result));
+        let timer = Instant::now();
+        let result = closest_dijkstra_with_queue::<QuadHeap<u32>>(map, from, to);
+        report("Djikstra, 4-ary heap", timer, visited_count(&result));
+
+        let timer = Instant::now();
+        let result = alt_with_queue::<LazyBinaryHeap<u32>>(map, waypoints, from, to);
+        report("Alt, binary heap", timer, visited_count(&result));
+        let timer = Instant::now();
+        let result = alt_with_queue::<QuadHeap<u32>>(map, waypoints, from, to);
+        report("Alt, 4-ary heap", timer, visited_count(&result));
+    }
+
+    if let Some(&(source, _)) = pairs.first() {
+        println!("\nFull searches from {}", map.get_name(source));
+        let timer = Instant::now();
+        full_dijkstra_with_queue::<LazyBinaryHeap<u32>>(map, source);
+        report("Full djikstra, binary heap", timer, map.nodes.len());
+        let timer = Instant::now();
+        full_dijkstra_with_queue::<QuadHeap<u32>>(map, source);
+        report("Full djikstra, 4-ary heap", timer, map.nodes.len());
+        let timer = Instant::now();
+        full_dijkstra_with_queue::<RadixHeap>(map, source);
+        report("Full djikstra, radix heap", timer, map.nodes.len());
+
+        const PLACE_TO_EAT: u8 = 8;
+        let source = source as usize;
+        let timer = Instant::now();
+        category_based_dijkstra_with_queue::<LazyBinaryHeap<u32>>(map, source, PLACE_TO_EAT, 100);
+        report("100 places to eat, binary heap", timer, 100);
+        let timer = Instant::now();
+        category_based_dijkstra_with_queue::<QuadHeap<u32>>(map, source, PLACE_TO_EAT, 100);
+        report("100 places to eat, 4-ary heap", timer, 100);
+    }
 }
 
-fn main() {
-    println!("Loading map ...");
-    let map = get_map_from_paths(
-        "norden_noder.txt",
-        "norden_kanter.txt",
-        "norden_interessepkt.txt",
-    )
-    .expect("Could Not load map");
-    println!("Done loading map.");
+/// Removes a flag without a value, returns whether it was given.
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let position = args.iter().position(|a| a == name);
+    if let Some(i) = position {
+        args.remove(i);
+    }
+    position.is_some()
+}
+
+/// Removes `name VALUE` from the arguments and returns the value.
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == name)?;
+    let value = args.get(i + 1).cloned();
+    args.drain(i..(i + 2).min(args.len()));
+    Some(value.unwrap_or_else(|| panic!("Missing value for {}", name)))
+}
 
-    println!("Loading waypoints ...");
-    let waypoint_timer = Instant::now();
-    let waypoints = get_waypoints(&map);
-    let waypoint_time = waypoint_timer.elapsed().as_millis() as f64 / 1000.0;
-    println!("Creating waypoints took {} seconds", waypoint_time);
-    println!("Done loading waypoints.");
+/// Removes `--depart TIME`, `--arrive TIME` and `--language en|nb` from the arguments.
+fn take_schedule(args: &mut Vec<String>) -> (Option<Schedule>, Language) {
+    let parse = |time: String| {
+        Timestamp::parse(&time).expect("Could not parse time, use like 2024-05-17T08:30")
+    };
+    let departure = take_option(args, "--depart").map(|t| Schedule::DepartAt(parse(t)));
+    let arrival = take_option(args, "--arrive").map(|t| Schedule::ArriveBy(parse(t)));
+    assert!(
+        departure.is_none() || arrival.is_none(),
+        "Give either a departure or an arrival time"
+    );
+    let language = take_option(args, "--language").map_or(Language::English, |name| {
+        Language::from_name(&name).expect("Unknown language, use en or nb")
+    });
+    (departure.or(arrival), language)
+}
 
-    const KÅRVÅG: u32 = 3292784;
-    const GJEMNES: u32 = 7352330;
+/// Prints the drive time, and the departure and arrival if there is a schedule.
+fn print_schedule(schedule: &Option<Schedule>, language: Language, centi_seconds: usize) {
+    let duration = TravelDuration::from_centi_seconds(centi_seconds as u64);
+    let label = match language {
+        Language::English => "Estimated travel time is:",
+        Language::Norwegian => "Estimert reisetid:",
+    };
+    println!("{} {} ({})", label, duration.to_text(language), duration.to_clock());
+    if let Some(schedule) = schedule {
+        println!("{}", schedule.describe(duration, language));
+    }
+}
 
-    const TAMPERE: u32 = 232073;
-    const ÅLESUND: u32 = 2518780;
+const KÅRVÅG: u32 = 3292784;
+const GJEMNES: u32 = 7352330;
 
+const TAMPERE: u32 = 232073;
+const ÅLESUND: u32 = 2518780;
 
-    compare_alt_and_dijkstras(&map, &waypoints, KÅRVÅG, GJEMNES);
-    compare_alt_and_dijkstras(&map, &waypoints, TAMPERE, ÅLESUND);
-    find_closest_information(&map);
-}
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut profile = VehicleProfile::car();
+    if let Some(name) = take_option(&mut args, "--vehicle") {
+        profile = VehicleProfile::from_name(&name)
+            .expect("Unknown vehicle, use car, truck, bus or tractor");
+    }
+    let prefix = take_option(&mut args, "--map").unwrap_or_else(|| "norden".to_owned());
+    let queue = take_option(&mut args, "--queue").map_or(QueueKind::Quad, |name| {
+        QueueKind::from_name(&name).expect("Unknown queue, use binary, quad or radix")
+    });
+    let workers = take_option(&mut args, "--workers").map_or_else(
+        || thread::available_parallelism().map_or(4, |n| n.get()),
+        |n| n.parse().expect("Could not parse worker count"),
+    );
+    let quantised = take_flag(&mut args, "--quantise");
+    let landmarks = take_option(&mut args, "--landmarks").map_or(Landmarks::farthest(), |name| {
+        Landmarks::from_name(&name).expect("Could not parse landmarks, use a count or A,B,...")
+    });
 
+    // Commands that make a map instead of reading one
+    if args.first().map(String::as_str) == Some("import") {
+        import_openstreetmap(&args[1..]);
+        return;
+    }
 
+    println!("Loading map ...");
+    let mut map = Map::load(&prefix).expect("Could Not load map");
+    if profile != VehicleProfile::car() {
+        println!("Using drive times for {}", profile.name);
+        map = map.get_profile_copy(&profile);
+    }
+    println!("Done loading map.");
 
+    let load_waypoints = || {
+        println!("Loading waypoints ...");
+        let waypoint_timer = Instant::now();
+        let path = profile.waypoint_path(&prefix);
+        let waypoints = get_waypoints(&map, &path, &landmarks, queue, workers, quantised)
+            .expect("Could not load or create waypoints");
+        let waypoint_time = waypoint_timer.elapsed().as_millis() as f64 / 1000.0;
+        println!("Creating waypoints took {} seconds", waypoint_time);
+        println!(
+            "Using {} {} waypoints: {:?}",
+            waypoints.sources().len(),
+            if waypoints.is_quantised() { "quantised" } else { "exact" },
+            waypoints.sources()
+        );
+        println!("Done loading waypoints.");
+        waypoints
+    };
+
+    match args.first().map(String::as_str) {
+        Some("arc-flags") => compare_alt_and_arc_flags(
+            &map,
+            &load_waypoints(),
+            &profile.arc_flag_path(&prefix),
+            workers,
+            &args[1..],
+        ),
+        Some("avoid") => route_with_overlay(&map, &load_waypoints(), &args[1..]),
+        Some("detour") => find_detours(&map, &args[1..]),
+        Some("directions") => print_directions(&map, &args[1..]),
+        Some("ev") => route_electric_vehicle(&map, &args[1..]),
+        Some("components") => report_components(&map),
+        Some("extract") => extract_region(&map, &args[1..]),
+        Some("hubs") => route_with_hub_labels(&map, &profile.hub_label_path(&prefix), &args[1..]),
+        Some("info" | "validate") => validate_map(&map, &args[1..]),
+        Some("match") => match_gps_trace(&map, &args[1..]),
+        Some("matrix") => create_travel_time_matrix(&map, &args[1..]),
+        Some("meet") => find_meeting_point(&map, &args[1..]),
+        Some("raster") => export_travel_time_raster(&map, &args[1..]),
+        Some("render") => render_searches(&map, &load_waypoints(), &args[1..]),
+        Some("benchmark") => {
+            benchmark_searches(&map, &load_waypoints(), &profile, &prefix, workers, &args[1..])
+        }
+        Some("bench-queues") => {
+            let pairs: Vec<(u32, u32)> = if args.len() > 1 {
+                parse_node_list(&args[1..].join(","))
+                    .chunks_exact(2)
+                    .map(|p| (p[0], p[1]))
+                    .collect()
+            } else {
+                vec![(KÅRVÅG, GJEMNES), (TAMPERE, ÅLESUND)]
+            };
+            benchmark_queues(&map, &load_waypoints(), &pairs);
+        }
+        Some(command) => println!("Unknown command: {}", command),
+        None => {
+            let waypoints = load_waypoints();
+            compare_alt_and_dijkstras(&map, &waypoints, KÅRVÅG, GJEMNES);
+            compare_alt_and_dijkstras(&map, &waypoints, TAMPERE, ÅLESUND);
+            find_closest_information(&map);
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use crate::geo::haversine_distance;
+use crate::spatial::GridIndex;
+use crate::Map;
+
+/// Settings of the hidden Markov model, distances in meters. The defaults follow Newson and
+/// Krumm, "Hidden Markov Map Matching Through Noise and Sparseness" (2009).
+#[derive(Debug, Clone, Copy)]
+pub struct MatchingParameters {
+    /// Standard deviation of the GPS noise.
+    pub gps_sigma: f64,
+    /// Scale of the exponential distribution of |network distance - straight-line distance|.
+    pub beta: f64,
+    /// Nodes further than this from a fix are not candidates for it.
+    pub search_radius: f64,
+    pub max_candidates: usize,
+    /// Network searches between two fixes stop after `route_factor` times the straight-line
+    /// distance plus `route_slack`.
+    pub route_factor: f64,
+    pub route_slack: f64,
+}
+
+impl Default for MatchingParameters {
+    fn default() -> Self {
+        Self {
+            gps_sigma: 4.07,
+            beta: 30.0,
+            search_radius: 50.0,
+            max_candidates: 8,
+            route_factor: 3.0,
+            route_slack: 500.0,
+        }
+    }
+}
+
+pub struct MatchedPoint {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// None when no node was near enough, or the fix could not be connected to its neighbours.
+    pub node: Option<u32>,
+    /// Meters from the fix to the node.
+    pub distance: f64,
+    /// Probability of the node given the whole trace, 0 when unmatched.
+    pub confidence: f64,
+}
+
+pub struct MatchResult {
+    pub points: Vec<MatchedPoint>,
+    /// Nodes driven, with the network paths between matched fixes filled in. Parts of the trace
+    /// that could not be connected follow each other directly.
+    pub path: Vec<u32>,
+}
+
+impl MatchResult {
+    pub fn write_csv(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "Latitude,Longitude,Node,Distance,Confidence")?;
+        for point in &self.points {
+            match point.node {
+                Some(node) => writeln!(
+                    writer,
+                    "{},{},{},{:.1},{:.4}",
+                    point.latitude, point.longitude, node, point.distance, point.confidence
+                )?,
+                None => writeln!(writer, "{},{},,,0", point.latitude, point.longitude)?,
+            }
+        }
+        writer.flush()
+    }
+}
+
+/// Reads "latitude,longitude" lines like `travel_path_to_csv` writes. Lines that do not start
+/// with two numbers, such as the header, are skipped.
+pub fn read_trace(path: &str) -> io::Result<Vec<(f64, f64)>> {
+    let mut trace = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        let mut columns = line.split(',').map(|c| c.trim().parse::<f64>());
+        if let (Some(Ok(latitude)), Some(Ok(longitude))) = (columns.next(), columns.next()) {
+            trace.push((latitude, longitude));
+        }
+    }
+    Ok(trace)
+}
+
+/// Dijkstra on edge length that stops at `budget` meters.
+///
+/// Returns for every reached node: (meters, previous node).
+fn length_limited_dijkstra(
+    map: &Map,
+    source: usize,
+    budget: u32,
+) -> HashMap<usize, (u32, Option<usize>)> {
+    let mut reached: HashMap<usize, (u32, Option<usize>)> = HashMap::new();
+    let mut priority_queue = BinaryHeap::new();
+    reached.insert(source, (0, None));
+    priority_queue.push(Reverse((0, source)));
+
+    while let Some(Reverse((meters, number))) = priority_queue.pop() {
+        if meters > reached[&number].0 {
+            continue;
+        }
+        for neighbour in &map.edges[number] {
+            let alt = meters + neighbour.length;
+            let to = neighbour.to as usize;
+            if alt <= budget && reached.get(&to).is_none_or(|r| alt < r.0) {
+                reached.insert(to, (alt, Some(number)));
+                priority_queue.push(Reverse((alt, to)));
+            }
+        }
+    }
+    reached
+}
+
+fn log_sum_exp(values: impl Iterator<Item = f64>) -> f64 {
+    let values: Vec<f64> = values.collect();
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + values.iter().map(|v| (v - max).exp()).sum::<f64>().ln()
+}
+
+/// Consecutive fixes whose candidates connect, with everything in log probabilities.
+#[derive(Default)]
+struct Chain {
+    fixes: Vec<usize>,
+    emissions: Vec<Vec<f64>>,
+    /// `transitions[k][i][j]` from candidate i of fix k to candidate j of fix k + 1.
+    transitions: Vec<Vec<Vec<f64>>>,
+    /// Viterbi scores and the candidate each one came from.
+    best: Vec<Vec<f64>>,
+    came_from: Vec<Vec<usize>>,
+}
+
+struct Matcher<'a> {
+    map: &'a Map,
+    trace: &'a [(f64, f64)],
+    parameters: MatchingParameters,
+    candidates: Vec<Vec<(u32, f64)>>,
+    result: MatchResult,
+}
+
+impl Matcher<'_> {
+    fn budget(&self, from: usize, to: usize) -> u32 {
+        let (lat1, lon1) = self.trace[from];
+        let (lat2, lon2) = self.trace[to];
+        let straight = haversine_distance(lat1, lon1, lat2, lon2);
+        (straight * self.parameters.route_factor + self.parameters.route_slack) as u32
+    }
+
+    fn transitions(&self, from: usize, to: usize) -> Vec<Vec<f64>> {
+        let (lat1, lon1) = self.trace[from];
+        let (lat2, lon2) = self.trace[to];
+        let straight = haversine_distance(lat1, lon1, lat2, lon2);
+        let budget = self.budget(from, to);
+        self.candidates[from]
+            .iter()
+            .map(|&(node, _)| {
+                let reached = length_limited_dijkstra(self.map, node as usize, budget);
+                self.candidates[to]
+                    .iter()
+                    .map(|(target, _)| match reached.get(&(*target as usize)) {
+                        Some((meters, _)) => {
+                            -(*meters as f64 - straight).abs() / self.parameters.beta
+                        }
+                        None => f64::NEG_INFINITY,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Viterbi for the chosen nodes, forward-backward for their confidence.
+    fn finish(&mut self, chain: Chain) {
+        let Some(last) = chain.best.last() else {
+            return;
+        };
+        let mut state = (0..last.len())
+            .max_by(|a, b| last[*a].total_cmp(&last[*b]))
+            .expect("Fixes in a chain have candidates");
+        let mut states = vec![state; chain.fixes.len()];
+        for k in (1..chain.fixes.len()).rev() {
+            state = chain.came_from[k][state];
+            states[k - 1] = state;
+        }
+
+        let mut forward = vec![chain.emissions[0].clone()];
+        for k in 1..chain.fixes.len() {
+            let row = (0..chain.emissions[k].len())
+                .map(|j| {
+                    let incoming = forward[k - 1]
+                        .iter()
+                        .enumerate()
+                        .map(|(i, f)| f + chain.transitions[k - 1][i][j]);
+                    log_sum_exp(incoming) + chain.emissions[k][j]
+                })
+                .collect();
+            forward.push(row);
+        }
+        let mut backward = vec![Vec::new(); chain.fixes.len()];
+        backward[chain.fixes.len() - 1] = vec![0.0; last.len()];
+        for k in (0..chain.fixes.len() - 1).rev() {
+            backward[k] = (0..chain.emissions[k].len())
+                .map(|i| {
+                    log_sum_exp((0..chain.emissions[k + 1].len()).map(|j| {
+                        chain.transitions[k][i][j] + chain.emissions[k + 1][j] + backward[k + 1][j]
+                    }))
+                })
+                .collect();
+        }
+        let total = log_sum_exp(forward[chain.fixes.len() - 1].iter().copied());
+
+        for (k, &fix) in chain.fixes.iter().enumerate() {
+            let (node, distance) = self.candidates[fix][states[k]];
+            let point = &mut self.result.points[fix];
+            point.node = Some(node);
+            point.distance = distance;
+            point.confidence = (forward[k][states[k]] + backward[k][states[k]] - total).exp();
+
+            if k == 0 {
+                if self.result.path.last() != Some(&node) {
+                    self.result.path.push(node);
+                }
+                continue;
+            }
+            let previous_fix = chain.fixes[k - 1];
+            let previous_node = self.candidates[previous_fix][states[k - 1]].0 as usize;
+            let budget = self.budget(previous_fix, fix);
+            let reached = length_limited_dijkstra(self.map, previous_node, budget);
+            let mut path = vec![node];
+            let mut current = node as usize;
+            while current != previous_node {
+                current = reached[&current]
+                    .1
+                    .expect("Chosen transitions have a route");
+                path.push(current as u32);
+            }
+            self.result.path.extend(path.iter().rev().skip(1));
+        }
+    }
+}
+
+/// Matches the trace to nodes of the map with a hidden Markov model, see `MatchingParameters`.
+///
+/// Fixes without candidates, or whose candidates can not be reached from the previous fix
+/// within the search budget, split the trace into parts that are matched on their own.
+pub fn match_trace(
+    map: &Map,
+    index: &GridIndex,
+    trace: &[(f64, f64)],
+    parameters: MatchingParameters,
+) -> MatchResult {
+    let candidates = trace
+        .iter()
+        .map(|&(latitude, longitude)| {
+            let mut near = index.nodes_within(map, latitude, longitude, parameters.search_radius);
+            near.truncate(parameters.max_candidates);
+            near
+        })
+        .collect();
+    let points = trace
+        .iter()
+        .map(|&(latitude, longitude)| MatchedPoint {
+            latitude,
+            longitude,
+            node: None,
+            distance: 0.0,
+            confidence: 0.0,
+        })
+        .collect();
+    let mut matcher = Matcher {
+        map,
+        trace,
+        parameters,
+        candidates,
+        result: MatchResult {
+            points,
+            path: Vec::new(),
+        },
+    };
+
+    let mut chain = Chain::default();
+    for fix in 0..trace.len() {
+        if matcher.candidates[fix].is_empty() {
+            matcher.finish(std::mem::take(&mut chain));
+            continue;
+        }
+        let emissions: Vec<f64> = matcher.candidates[fix]
+            .iter()
+            .map(|(_, distance)| -0.5 * (distance / parameters.gps_sigma).powi(2))
+            .collect();
+
+        if let (Some(&previous_fix), Some(previous_best)) = (chain.fixes.last(), chain.best.last())
+        {
+            let transitions = matcher.transitions(previous_fix, fix);
+            let mut best = Vec::with_capacity(emissions.len());
+            let mut came_from = Vec::with_capacity(emissions.len());
+            for (j, emission) in emissions.iter().enumerate() {
+                let (i, score) = previous_best
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| (i, b + transitions[i][j]))
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .expect("Fixes in a chain have candidates");
+                best.push(score + emission);
+                came_from.push(i);
+            }
+            if best.iter().all(|b| *b == f64::NEG_INFINITY) {
+                matcher.finish(std::mem::take(&mut chain));
+            } else {
+                chain.transitions.push(transitions);
+                chain.fixes.push(fix);
+                chain.best.push(best);
+                chain.came_from.push(came_from);
+                chain.emissions.push(emissions);
+                continue;
+            }
+        }
+        chain.fixes.push(fix);
+        chain.best.push(emissions.clone());
+        chain.came_from.push(Vec::new());
+        chain.emissions.push(emissions);
+    }
+    matcher.finish(chain);
+    matcher.result
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::ControlFlow;
+use std::thread;
+
+use crate::graph::Search;
+use crate::{get_byte_array_from_u32, Map};
+
+/// Marks a pair without any route in `TravelTimeMatrix.times`.
+pub const UNREACHABLE: u32 = u32::MAX;
+
+/// Dense drive-time matrix in centiseconds, one row per source and one column per target.
+pub struct TravelTimeMatrix {
+    pub sources: Vec<u32>,
+    pub targets: Vec<u32>,
+    pub times: Vec<u32>,
+}
+
+impl TravelTimeMatrix {
+    pub fn get(&self, row: usize, column: usize) -> Option<u32> {
+        let time = self.times[row * self.targets.len() + column];
+        (time != UNREACHABLE).then_some(time)
+    }
+
+    /// One row per source, the first column holds the source id. Unreachable pairs are written
+    /// as "unreachable".
+    pub fn write_csv(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, "source")?;
+        for target in &self.targets {
+            write!(writer, ",{}", target)?;
+        }
+        writeln!(writer)?;
+        for (row, source) in self.sources.iter().enumerate() {
+            write!(writer, "{}", source)?;
+            for column in 0..self.targets.len() {
+                match self.get(row, column) {
+                    Some(time) => write!(writer, ",{}", time)?,
+                    None => write!(writer, ",unreachable")?,
+                }
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()
+    }
+
+    /// Big-endian u32s like the waypoint file: row count, column count, source ids, target ids,
+    /// then the times row by row with `UNREACHABLE` for missing routes.
+    pub fn write_binary(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&get_byte_array_from_u32(self.sources.len() as u32))?;
+        writer.write_all(&get_byte_array_from_u32(self.targets.len() as u32))?;
+        for value in self.sources.iter().chain(&self.targets).chain(&self.times) {
+            writer.write_all(&get_byte_array_from_u32(*value))?;
+        }
+        writer.flush()
+    }
+}
+
+/// Dijkstra from one source that stops once every target is settled. `columns` maps each
+/// target node to its columns in the row.
+fn one_to_many_dijkstra(
+    map: &Map,
+    search: &mut Search,
+    source: usize,
+    columns: &HashMap<u32, Vec<usize>>,
+    row: &mut [u32],
+) {
+    let mut remaining = columns.len();
+    search.run(map, &[source], |_| 0, |number, cost| {
+        let Some(target_columns) = columns.get(&(number as u32)) else {
+            return ControlFlow::Continue(());
+        };
+        for column in target_columns {
+            row[*column] = cost;
+        }
+        remaining -= 1;
+        if remaining == 0 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+}
+
+/// Drive times from every source to every target, with the sources split over `threads`
+/// scoped threads.
+pub fn travel_time_matrix(
+    map: &Map,
+    sources: &[u32],
+    targets: &[u32],
+    threads: usize,
+) -> TravelTimeMatrix {
+    let mut columns: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (column, target) in targets.iter().enumerate() {
+        columns.entry(*target).or_default().push(column);
+    }
+    let mut times = vec![UNREACHABLE; sources.len() * targets.len()];
+    if targets.is_empty() {
+        return TravelTimeMatrix {
+            sources: sources.to_vec(),
+            targets: targets.to_vec(),
+            times,
+        };
+    }
+
+    let rows_per_thread = sources.len().div_ceil(threads.max(1)).max(1);
+    let columns = &columns;
+    thread::scope(|scope| {
+        let chunks = sources
+            .chunks(rows_per_thread)
+            .zip(times.chunks_mut(rows_per_thread * targets.len()));
+        for (sources, times) in chunks {
+            scope.spawn(move || {
+                let mut search = Search::new(map.edges.len());
+                for (source, row) in sources.iter().zip(times.chunks_mut(targets.len())) {
+                    one_to_many_dijkstra(map, &mut search, *source as usize, columns, row);
+                }
+            });
+        }
+    });
+
+    TravelTimeMatrix {
+        sources: sources.to_vec(),
+        targets: targets.to_vec(),
+        times,
+    }
+}
@@ -0,0 +1,104 @@
+use std::thread;
+
+use crate::{full_dijkstra, Map, ShortestPathTree};
+
+/// What makes a meeting point best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    /// Lowest drive time of the traveller who drives the longest, so everyone arrives early.
+    Latest,
+    /// Lowest drive time of all the travellers together.
+    Total,
+}
+
+impl Objective {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "max" => Some(Self::Latest),
+            "sum" => Some(Self::Total),
+            _ => None,
+        }
+    }
+
+    fn cost(&self, times: impl Iterator<Item = u32>) -> u64 {
+        match self {
+            Self::Latest => times.max().unwrap_or(0) as u64,
+            Self::Total => times.map(u64::from).sum(),
+        }
+    }
+}
+
+pub struct MeetingPoint {
+    pub node: u32,
+    /// Value of the objective in centiseconds.
+    pub cost: u64,
+    /// Drive time and path of every traveller, in the order they were given.
+    pub times: Vec<u32>,
+    pub paths: Vec<Vec<u32>>,
+}
+
+/// The node every traveller can reach that is best by `objective`, or the best point of interest
+/// with all the bits of `category`. Ties go to the lowest node. Runs one `full_dijkstra` per
+/// traveller, each on its own thread. Returns None if there are no travellers or no such node
+/// can be reached by all of them.
+pub fn meeting_point(
+    map: &Map,
+    travellers: &[u32],
+    objective: Objective,
+    category: Option<u8>,
+) -> Option<MeetingPoint> {
+    if travellers.is_empty() {
+        return None;
+    }
+    let trees: Vec<ShortestPathTree> = thread::scope(|scope| {
+        let handles: Vec<_> = travellers
+            .iter()
+            .map(|source| scope.spawn(move || full_dijkstra(map, *source)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("Search thread panicked"))
+            .collect()
+    });
+
+    let candidates: Vec<usize> = match category {
+        Some(category) => {
+            let mut nodes: Vec<usize> = map
+                .points_of_interest
+                .iter()
+                .filter(|(_, (c, _))| c & category == category)
+                .map(|(node, _)| *node as usize)
+                .collect();
+            nodes.sort_unstable();
+            nodes
+        }
+        None => (0..map.edges.len()).collect(),
+    };
+    let mut best: Option<(u64, usize)> = None;
+    let mut times = Vec::with_capacity(trees.len());
+    for node in candidates {
+        times.clear();
+        times.extend(trees.iter().map_while(|t| t.distance_to(node)));
+        if times.len() < trees.len() {
+            continue;
+        }
+        let cost = objective.cost(times.iter().copied());
+        if best.is_none_or(|(lowest, _)| cost < lowest) {
+            best = Some((cost, node));
+        }
+    }
+
+    let (cost, node) = best?;
+    Some(MeetingPoint {
+        node: node as u32,
+        cost,
+        times: trees
+            .iter()
+            .map(|t| t.distance_to(node))
+            .collect::<Option<_>>()?,
+        paths: trees
+            .iter()
+            .map(|t| t.path_to(node))
+            .collect::<Option<_>>()?,
+    })
+}
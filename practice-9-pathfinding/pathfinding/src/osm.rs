@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+use crate::geo::haversine_distance;
+use crate::spatial::GridIndex;
+use crate::{EdgeTo, Map, Node};
+
+const GAS_STATION: u8 = 2;
+const CHARGING_STATION: u8 = 4;
+const PLACE_TO_EAT: u8 = 8;
+const PLACE_TO_DRINK: u8 = 16;
+
+/// Amenities further than this many meters from every road node are left out.
+const POI_SNAP_DISTANCE: f64 = 250.0;
+
+/// Default speed limit in km/h for the highway types that are imported.
+fn default_speed_limit(highway: &str) -> Option<u16> {
+    match highway {
+        "motorway" => Some(110),
+        "trunk" => Some(90),
+        "motorway_link" | "primary" | "secondary" => Some(80),
+        "trunk_link" => Some(70),
+        "tertiary" | "primary_link" => Some(60),
+        "secondary_link" | "tertiary_link" | "unclassified" | "road" => Some(50),
+        "residential" => Some(30),
+        "service" => Some(20),
+        "living_street" => Some(10),
+        _ => None,
+    }
+}
+
+/// Parses OSM `maxspeed` values such as "60", "30 mph", "NO:urban" or "50;70".
+fn parse_max_speed(value: &str) -> Option<u16> {
+    let value = value.split(';').next()?.trim();
+    if let Some(mph) = value.strip_suffix("mph") {
+        let mph: f64 = mph.trim().parse().ok()?;
+        return Some((mph * 1.609_344).round() as u16);
+    }
+    match value.split(':').nth(1) {
+        Some("urban") => Some(50),
+        Some("rural") => Some(80),
+        Some("motorway") => Some(110),
+        Some(_) => None,
+        None => value.parse().ok().filter(|speed| *speed > 0),
+    }
+}
+
+fn poi_category(amenity: &str) -> Option<u8> {
+    match amenity {
+        "fuel" => Some(GAS_STATION),
+        "charging_station" => Some(CHARGING_STATION),
+        "restaurant" | "fast_food" => Some(PLACE_TO_EAT),
+        "bar" | "pub" => Some(PLACE_TO_DRINK),
+        _ => None,
+    }
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match character {
+            Some(character) => {
+                result.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// One start, end or empty-element tag.
+struct XmlTag {
+    name: String,
+    attributes: Vec<(String, String)>,
+    is_end: bool,
+    is_empty: bool,
+}
+
+impl XmlTag {
+    /// Parses the text between '<' and '>'. Declarations and comments give None.
+    fn parse(text: &str) -> Option<Self> {
+        if text.starts_with('?') || text.starts_with('!') {
+            return None;
+        }
+        let is_end = text.starts_with('/');
+        let is_empty = text.ends_with('/');
+        let text = text.trim_start_matches('/').trim_end_matches('/');
+        let name_end = text.find(char::is_whitespace).unwrap_or(text.len());
+        let mut tag = Self {
+            name: text[..name_end].to_owned(),
+            attributes: Vec::new(),
+            is_end,
+            is_empty,
+        };
+
+        let mut rest = &text[name_end..];
+        while let Some(equals) = rest.find('=') {
+            let key = rest[..equals].trim().to_owned();
+            let value = rest[equals + 1..].trim_start();
+            let quote = value.chars().next()?;
+            let value_end = value[1..].find(quote)? + 1;
+            tag.attributes.push((key, unescape(&value[1..value_end])));
+            rest = &value[value_end + 1..];
+        }
+        Some(tag)
+    }
+
+    fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Default)]
+struct Element {
+    /// Node coordinates, None for ways.
+    coordinates: Option<(f64, f64)>,
+    node_refs: Vec<i64>,
+    tags: HashMap<String, String>,
+}
+
+/// Where an amenity is. Amenities mapped as ways are placed at the way's first node, which may
+/// come after the way in the file, so it is looked up at the end.
+enum Position {
+    Coordinates(f64, f64),
+    Node(i64),
+}
+
+struct Reader {
+    coordinates: HashMap<i64, (f64, f64)>,
+    /// (node refs, oneway direction, speed limit) of every imported highway.
+    ways: Vec<(Vec<i64>, Oneway, u16)>,
+    /// (position, category, name)
+    amenities: Vec<(Position, u8, String)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Oneway {
+    No,
+    Forward,
+    Backward,
+}
+
+impl Reader {
+    fn finish_element(&mut self, element: Element) {
+        if let Some(amenity) = element.tags.get("amenity") {
+            let position = match element.coordinates {
+                Some((latitude, longitude)) => Some(Position::Coordinates(latitude, longitude)),
+                None => element.node_refs.first().map(|n| Position::Node(*n)),
+            };
+            if let (Some(category), Some(position)) = (poi_category(amenity), position) {
+                // Quotes and tabs would break the POI file format
+                let name = element.tags.get("name").unwrap_or(amenity);
+                let name = name.replace('"', "'").replace('\t', " ");
+                self.amenities.push((position, category, name));
+            }
+        }
+
+        let Some(highway) = element.tags.get("highway") else {
+            return;
+        };
+        let Some(default_speed) = default_speed_limit(highway) else {
+            return;
+        };
+        if element.coordinates.is_some() || element.node_refs.len() < 2 {
+            return;
+        }
+        let speed_limit = element
+            .tags
+            .get("maxspeed")
+            .and_then(|m| parse_max_speed(m))
+            .unwrap_or(default_speed);
+        let implied_oneway = highway == "motorway"
+            || element
+                .tags
+                .get("junction")
+                .is_some_and(|j| j == "roundabout");
+        let oneway = match element.tags.get("oneway").map(String::as_str) {
+            Some("yes" | "true" | "1") => Oneway::Forward,
+            Some("-1" | "reverse") => Oneway::Backward,
+            Some(_) => Oneway::No,
+            None if implied_oneway => Oneway::Forward,
+            None => Oneway::No,
+        };
+        self.ways.push((element.node_refs, oneway, speed_limit));
+    }
+}
+
+/// Reads an OpenStreetMap XML extract into a map of its drivable highways.
+///
+/// Ways are split into one edge per pair of consecutive nodes, in both directions unless they
+/// are one-way. Only nodes used by an imported way are kept, numbered by ascending OSM id.
+/// Fuel, charging, eating and drinking amenities become points of interest on the closest
+/// road node.
+///
+/// Returns: (map, OSM id of every node)
+pub fn import_osm(path: &str) -> io::Result<(Map, Vec<i64>)> {
+    let mut reader = Reader {
+        coordinates: HashMap::new(),
+        ways: Vec::new(),
+        amenities: Vec::new(),
+    };
+    let mut input = BufReader::new(File::open(path)?);
+    let mut buffer = Vec::new();
+    let mut element: Option<Element> = None;
+    loop {
+        buffer.clear();
+        if input.read_until(b'>', &mut buffer)? == 0 {
+            break;
+        }
+        let chunk = String::from_utf8_lossy(&buffer);
+        let Some(start) = chunk.rfind('<') else {
+            continue;
+        };
+        let Some(tag) = XmlTag::parse(chunk[start + 1..].trim_end_matches('>')) else {
+            continue;
+        };
+
+        match (tag.name.as_str(), tag.is_end) {
+            ("node", false) => {
+                let id: Option<i64> = tag.attribute("id").and_then(|v| v.parse().ok());
+                let latitude: Option<f64> = tag.attribute("lat").and_then(|v| v.parse().ok());
+                let longitude: Option<f64> = tag.attribute("lon").and_then(|v| v.parse().ok());
+                if let (Some(id), Some(latitude), Some(longitude)) = (id, latitude, longitude) {
+                    reader.coordinates.insert(id, (latitude, longitude));
+                }
+                let node = Element {
+                    coordinates: Some((latitude.unwrap_or(0.0), longitude.unwrap_or(0.0))),
+                    ..Element::default()
+                };
+                if tag.is_empty {
+                    reader.finish_element(node);
+                } else {
+                    element = Some(node);
+                }
+            }
+            ("way", false) => element = Some(Element::default()),
+            ("nd", false) => {
+                if let (Some(way), Some(node)) = (&mut element, tag.attribute("ref")) {
+                    if let Ok(node) = node.parse() {
+                        way.node_refs.push(node);
+                    }
+                }
+            }
+            ("tag", false) => {
+                if let (Some(current), Some(key), Some(value)) =
+                    (&mut element, tag.attribute("k"), tag.attribute("v"))
+                {
+                    current.tags.insert(key.to_owned(), value.to_owned());
+                }
+            }
+            ("node" | "way", true) => {
+                if let Some(finished) = element.take() {
+                    reader.finish_element(finished);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Number the nodes used by roads, skipping references to nodes outside the extract
+    let mut osm_ids: Vec<i64> = reader
+        .ways
+        .iter()
+        .flat_map(|(refs, _, _)| refs.iter().copied())
+        .filter(|id| reader.coordinates.contains_key(id))
+        .collect();
+    osm_ids.sort_unstable();
+    osm_ids.dedup();
+    let numbers: HashMap<i64, u32> = osm_ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (*id, i as u32))
+        .collect();
+    let nodes: Vec<Node> = osm_ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| {
+            let (latitude, longitude) = reader.coordinates[id];
+            Node::new(i as u32, latitude, longitude)
+        })
+        .collect();
+
+    let mut edges = vec![Vec::new(); nodes.len()];
+    for (refs, oneway, speed_limit) in &reader.ways {
+        for pair in refs.windows(2) {
+            let (Some(&from), Some(&to)) = (numbers.get(&pair[0]), numbers.get(&pair[1])) else {
+                continue;
+            };
+            let (a, b) = (&nodes[from as usize], &nodes[to as usize]);
+            let length =
+                haversine_distance(a.latitude, a.longitude, b.latitude, b.longitude).round() as u32;
+            let drive_time = length * 360 / *speed_limit as u32;
+            if *oneway != Oneway::Backward {
+                edges[from as usize].push(EdgeTo::new(to, drive_time, length, *speed_limit));
+            }
+            if *oneway != Oneway::Forward {
+                edges[to as usize].push(EdgeTo::new(from, drive_time, length, *speed_limit));
+            }
+        }
+    }
+    let mut map = Map::from_nodes_edges_and_poi(nodes, edges, HashMap::new());
+
+    let index = GridIndex::new(&map, POI_SNAP_DISTANCE);
+    for (position, amenity_category, name) in reader.amenities {
+        let (latitude, longitude) = match position {
+            Position::Coordinates(latitude, longitude) => (latitude, longitude),
+            Position::Node(node) => match reader.coordinates.get(&node) {
+                Some(&coordinates) => coordinates,
+                None => continue,
+            },
+        };
+        let near = index.nodes_within(&map, latitude, longitude, POI_SNAP_DISTANCE);
+        let Some(&(node, _)) = near.first() else {
+            continue;
+        };
+        // Several amenities on one node share it, with the categories combined
+        map.points_of_interest
+            .entry(node)
+            .and_modify(|(category, _)| *category |= amenity_category)
+            .or_insert((amenity_category, name));
+    }
+    Ok((map, osm_ids))
+}
@@ -0,0 +1,139 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::geo::Polygon;
+use crate::graph::{point_to_point, Filtered};
+use crate::queue::DefaultQueue;
+use crate::waypoints::Waypoints;
+use crate::{EdgeTo, Map, SearchResult, UNREACHED};
+
+/// Highest drive time a penalty gives an edge, about 15 days. Routes over up to 15 such edges
+/// still add up to less than `UNREACHED`, so a penalized road is avoided but never closed.
+pub const MAX_PENALIZED_DRIVE_TIME: u32 = UNREACHED / 16;
+
+/// Road closures and penalties applied on top of `Map.edges` at query time.
+///
+/// Blocking an edge blocks every parallel edge between the two nodes. Nodes that are
+/// blocked or lie inside an avoid-zone can not be entered, but a query may start in one.
+#[derive(Debug, Clone, Default)]
+pub struct Overlay {
+    blocked_edges: HashSet<(u32, u32)>,
+    blocked_nodes: HashSet<u32>,
+    avoid_zones: Vec<Polygon>,
+    penalties: HashMap<(u32, u32), f64>,
+}
+
+impl Overlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn block_edge(&mut self, from: u32, to: u32) -> &mut Self {
+        self.blocked_edges.insert((from, to));
+        self
+    }
+
+    pub fn block_node(&mut self, node: u32) -> &mut Self {
+        self.blocked_nodes.insert(node);
+        self
+    }
+
+    pub fn avoid_zone(&mut self, zone: Polygon) -> &mut Self {
+        self.avoid_zones.push(zone);
+        self
+    }
+
+    /// Multiplies the drive time of the edge. Factors below 1.0 are allowed for Dijkstra only.
+    /// Penalized drive times are capped at `MAX_PENALIZED_DRIVE_TIME`, so a huge factor makes
+    /// the searches avoid the edge when there is any other way, but they still use it when
+    /// there is none. Use `block_edge` to close it.
+    pub fn penalize_edge(&mut self, from: u32, to: u32, factor: f64) -> &mut Self {
+        assert!(factor >= 0.0, "Penalty factor can not be negative");
+        assert!(factor.is_finite(), "Penalty factor must be finite");
+        self.penalties.insert((from, to), factor);
+        self
+    }
+
+    /// The landmark bounds stay valid as long as the overlay never makes an edge cheaper.
+    pub fn keeps_alt_admissible(&self) -> bool {
+        self.penalties.values().all(|&factor| factor >= 1.0)
+    }
+
+    /// Resolves blocked nodes and avoid-zones against the node coordinates once per query.
+    pub fn apply<'a>(&'a self, map: &Map) -> AppliedOverlay<'a> {
+        let mut node_blocked = vec![false; map.edges.len()];
+        for (i, node) in map.nodes.iter().enumerate() {
+            node_blocked[i] = self.blocked_nodes.contains(&(i as u32))
+                || self
+                    .avoid_zones
+                    .iter()
+                    .any(|zone| zone.contains(node.latitude, node.longitude));
+        }
+        AppliedOverlay {
+            overlay: self,
+            node_blocked,
+        }
+    }
+}
+
+pub struct AppliedOverlay<'a> {
+    overlay: &'a Overlay,
+    node_blocked: Vec<bool>,
+}
+
+impl AppliedOverlay<'_> {
+    pub fn is_node_blocked(&self, node: usize) -> bool {
+        self.node_blocked[node]
+    }
+
+    /// Returns the drive time of the edge with the overlay applied, or None if it is closed.
+    pub fn edge_cost(&self, from: usize, edge: &EdgeTo) -> Option<u32> {
+        if self.node_blocked[edge.to as usize] {
+            return None;
+        }
+        let key = (from as u32, edge.to);
+        if self.overlay.blocked_edges.contains(&key) {
+            return None;
+        }
+        match self.overlay.penalties.get(&key) {
+            Some(factor) => {
+                let drive_time = (edge.drive_time as f64 * factor).ceil();
+                Some(drive_time.min(MAX_PENALIZED_DRIVE_TIME as f64) as u32)
+            }
+            None => Some(edge.drive_time),
+        }
+    }
+}
+
+/// Same as `closest_dijkstra`, with the closures and penalties of the overlay.
+pub fn closest_dijkstra_with_overlay(
+    map: &Map,
+    overlay: &AppliedOverlay,
+    from: usize,
+    to: usize,
+) -> Option<SearchResult> {
+    let graph = Filtered::new(map, |number, edge| overlay.edge_cost(number, edge));
+    point_to_point::<DefaultQueue>(&graph, from, to, |_| 0)
+}
+
+/// Same as `alt`, with the closures and penalties of the overlay.
+///
+/// The landmarks are computed on the unrestricted map. Closing edges or making them more
+/// expensive can only increase the true distances, so the landmark lower bounds stay
+/// admissible and consistent. A penalty factor below 1.0 breaks this, which is why it is
+/// rejected here; use `closest_dijkstra_with_overlay` for such overlays instead.
+pub fn alt_with_overlay(
+    map: &Map,
+    waypoints: &Waypoints,
+    overlay: &AppliedOverlay,
+    source: usize,
+    goal: usize,
+) -> Option<SearchResult> {
+    assert!(
+        overlay.overlay.keeps_alt_admissible(),
+        "ALT needs every penalty factor to be at least 1.0"
+    );
+    let graph = Filtered::new(map, |number, edge| overlay.edge_cost(number, edge));
+    point_to_point::<DefaultQueue>(&graph, source, goal, |node| {
+        waypoints.lower_bound(node, goal)
+    })
+}
@@ -0,0 +1,150 @@
+use crate::{EdgeTo, Map};
+
+/// Speed restrictions for a type of vehicle.
+///
+/// `speed_bands` is a list of (highest speed limit, factor), sorted by speed limit. An edge
+/// uses the factor of the first band covering its speed limit, and 1.0 if there is none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VehicleProfile {
+    pub name: String,
+    pub max_speed: Option<u16>,
+    pub speed_bands: Vec<(u16, f64)>,
+    /// Lets recomputed drive times come out below the ones in the map, which are usually
+    /// slower than the speed limit on roads with curves or junctions.
+    pub faster_than_map: bool,
+}
+
+impl VehicleProfile {
+    pub fn new(name: &str, max_speed: Option<u16>, speed_bands: Vec<(u16, f64)>) -> Self {
+        Self {
+            name: name.to_owned(),
+            max_speed,
+            speed_bands,
+            faster_than_map: false,
+        }
+    }
+
+    pub fn allow_faster_than_map(mut self) -> Self {
+        self.faster_than_map = true;
+        self
+    }
+
+    pub fn car() -> Self {
+        Self::new("car", None, Vec::new())
+    }
+
+    pub fn truck() -> Self {
+        Self::new("truck", Some(80), vec![(30, 0.8), (60, 0.9)])
+    }
+
+    pub fn bus() -> Self {
+        Self::new("bus", Some(90), vec![(30, 0.85), (60, 0.95)])
+    }
+
+    pub fn tractor() -> Self {
+        Self::new("tractor", Some(40), Vec::new())
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "car" => Some(Self::car()),
+            "truck" => Some(Self::truck()),
+            "bus" => Some(Self::bus()),
+            "tractor" => Some(Self::tractor()),
+            _ => None,
+        }
+    }
+
+    /// Landmarks depend on the map and the edge costs, so every map prefix and profile gets its
+    /// own file next to the map files. Profiles other than the car are told apart by a hash of
+    /// their speeds as well as their name, so a changed profile never reads another one's file.
+    pub fn waypoint_path(&self, prefix: &str) -> String {
+        self.cache_path(prefix, "waypoints")
+    }
+
+    pub fn hub_label_path(&self, prefix: &str) -> String {
+        self.cache_path(prefix, "hub_labels")
+    }
+
+    pub fn arc_flag_path(&self, prefix: &str) -> String {
+        self.cache_path(prefix, "arc_flags")
+    }
+
+    fn cache_path(&self, prefix: &str, kind: &str) -> String {
+        if *self == Self::car() {
+            format!("{}_{}.bin", prefix, kind)
+        } else {
+            let hash = self.speed_hash();
+            format!("{}_{}_{}_{:08x}.bin", prefix, kind, self.name, hash)
+        }
+    }
+
+    /// FNV-1a over everything but the name that changes the drive times. Unlike the hashers in
+    /// std it gives the same file names on every build.
+    fn speed_hash(&self) -> u32 {
+        let mut bytes = self.max_speed.map_or(vec![0], |speed| {
+            let mut bytes = vec![1];
+            bytes.extend(speed.to_be_bytes());
+            bytes
+        });
+        for (highest, factor) in &self.speed_bands {
+            bytes.extend(highest.to_be_bytes());
+            bytes.extend(factor.to_bits().to_be_bytes());
+        }
+        bytes.push(self.faster_than_map as u8);
+        bytes.iter().fold(0x811c_9dc5, |hash, byte| {
+            (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+        })
+    }
+
+    fn speed_factor(&self, speed_limit: u16) -> f64 {
+        self.speed_bands
+            .iter()
+            .find(|(highest, _)| speed_limit <= *highest)
+            .map_or(1.0, |(_, factor)| *factor)
+    }
+
+    /// Drive time in centiseconds. Edges the vehicle can drive at the speed limit keep their
+    /// precomputed drive time, the rest are recomputed from the length, but never below the
+    /// precomputed one unless `faster_than_map` is set.
+    pub fn drive_time(&self, edge: &EdgeTo) -> u32 {
+        let mut speed = edge.speed_limit as f64 * self.speed_factor(edge.speed_limit);
+        if let Some(max_speed) = self.max_speed {
+            speed = speed.min(max_speed as f64);
+        }
+        if speed >= edge.speed_limit as f64 || speed <= 0.0 {
+            return edge.drive_time;
+        }
+        // length [m] / (speed [km/h] / 3.6) [m/s] * 100 [cs/s]
+        let drive_time = (edge.length as f64 * 360.0 / speed).round() as u32;
+        if self.faster_than_map {
+            drive_time
+        } else {
+            drive_time.max(edge.drive_time)
+        }
+    }
+}
+
+impl Map {
+    /// Copy of the map where every drive time is the one for the given vehicle.
+    pub fn get_profile_copy(&self, profile: &VehicleProfile) -> Self {
+        let edges = self
+            .edges
+            .iter()
+            .map(|edges| {
+                edges
+                    .iter()
+                    .map(|edge| {
+                        EdgeTo::new(
+                            edge.to,
+                            profile.drive_time(edge),
+                            edge.length,
+                            edge.speed_limit,
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+        Map::from_nodes_edges_and_poi(self.nodes.clone(), edges, self.points_of_interest.clone())
+    }
+}
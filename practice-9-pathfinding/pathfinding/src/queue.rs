@@ -0,0 +1,239 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const NOT_IN_HEAP: u32 = u32::MAX;
+
+/// Min-priority queue over node numbers, with at most one entry per node.
+///
+/// `push` on a node already in the queue only lowers its cost. A node that has been popped can
+/// be pushed again, it is up to the search not to do that for settled nodes.
+pub trait PriorityQueue<T> {
+    fn new(node_count: usize) -> Self;
+    fn push(&mut self, node: usize, cost: T);
+    fn pop(&mut self) -> Option<(usize, T)>;
+    /// Empties the queue so it can be reused for another search on the same map.
+    fn clear(&mut self);
+}
+
+/// Indexed d-ary heap with decrease-key. A position table of one u32 per node tells where each
+/// node sits in the heap, so a node is never in the heap twice and never expanded twice.
+pub struct IndexedDaryHeap<T, const D: usize> {
+    heap: Vec<(T, u32)>,
+    positions: Vec<u32>,
+}
+
+pub type QuadHeap<T> = IndexedDaryHeap<T, 4>;
+
+/// Queue of the searches that do not take one. The `_with_queue` searches and `--queue` pick
+/// `LazyBinaryHeap` or `RadixHeap` instead, and `bench-queues` compares them on a map.
+pub type DefaultQueue = QuadHeap<u32>;
+
+impl<T: Copy + Ord, const D: usize> IndexedDaryHeap<T, D> {
+    fn sift_up(&mut self, mut i: usize) {
+        let item = self.heap[i];
+        while i > 0 {
+            let parent = (i - 1) / D;
+            if self.heap[parent].0 <= item.0 {
+                break;
+            }
+            self.heap[i] = self.heap[parent];
+            self.positions[self.heap[i].1 as usize] = i as u32;
+            i = parent;
+        }
+        self.heap[i] = item;
+        self.positions[item.1 as usize] = i as u32;
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        let item = self.heap[i];
+        let length = self.heap.len();
+        loop {
+            let first = i * D + 1;
+            if first >= length {
+                break;
+            }
+            let mut smallest = first;
+            for child in first + 1..(first + D).min(length) {
+                if self.heap[child].0 < self.heap[smallest].0 {
+                    smallest = child;
+                }
+            }
+            if self.heap[smallest].0 >= item.0 {
+                break;
+            }
+            self.heap[i] = self.heap[smallest];
+            self.positions[self.heap[i].1 as usize] = i as u32;
+            i = smallest;
+        }
+        self.heap[i] = item;
+        self.positions[item.1 as usize] = i as u32;
+    }
+}
+
+impl<T: Copy + Ord, const D: usize> PriorityQueue<T> for IndexedDaryHeap<T, D> {
+    fn new(node_count: usize) -> Self {
+        Self {
+            heap: Vec::new(),
+            positions: vec![NOT_IN_HEAP; node_count],
+        }
+    }
+
+    fn push(&mut self, node: usize, cost: T) {
+        let position = self.positions[node];
+        if position == NOT_IN_HEAP {
+            self.heap.push((cost, node as u32));
+            self.sift_up(self.heap.len() - 1);
+        } else if cost < self.heap[position as usize].0 {
+            self.heap[position as usize].0 = cost;
+            self.sift_up(position as usize);
+        }
+    }
+
+    fn pop(&mut self) -> Option<(usize, T)> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        let (cost, node) = self.heap.swap_remove(0);
+        self.positions[node as usize] = NOT_IN_HEAP;
+        if !self.heap.is_empty() {
+            self.sift_down(0);
+        }
+        Some((node as usize, cost))
+    }
+
+    fn clear(&mut self) {
+        for (_, node) in self.heap.drain(..) {
+            self.positions[node as usize] = NOT_IN_HEAP;
+        }
+    }
+}
+
+/// `std::collections::BinaryHeap` without decrease-key. Lowering a cost pushes a second entry,
+/// and entries that no longer match the node's current cost are skipped when popped.
+pub struct LazyBinaryHeap<T> {
+    heap: BinaryHeap<Reverse<(T, usize)>>,
+    costs: Vec<Option<T>>,
+}
+
+impl<T: Copy + Ord> PriorityQueue<T> for LazyBinaryHeap<T> {
+    fn new(node_count: usize) -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            costs: vec![None; node_count],
+        }
+    }
+
+    fn push(&mut self, node: usize, cost: T) {
+        if self.costs[node].is_none_or(|current| cost < current) {
+            self.costs[node] = Some(cost);
+            self.heap.push(Reverse((cost, node)));
+        }
+    }
+
+    fn pop(&mut self) -> Option<(usize, T)> {
+        while let Some(Reverse((cost, node))) = self.heap.pop() {
+            if self.costs[node] == Some(cost) {
+                self.costs[node] = None;
+                return Some((node, cost));
+            }
+        }
+        None
+    }
+
+    fn clear(&mut self) {
+        for Reverse((_, node)) in self.heap.drain() {
+            self.costs[node] = None;
+        }
+    }
+}
+
+/// Monotone radix heap for u32 costs. Every pushed cost must be at least the last popped one,
+/// which holds for Dijkstra with non-negative edges. Entries sit in 33 buckets by the highest
+/// bit in which they differ from the last popped cost, so each entry is moved at most 32 times.
+pub struct RadixHeap {
+    buckets: Vec<Vec<(u32, u32)>>,
+    /// Spare bucket swapped in while redistributing, so no bucket loses its allocation.
+    spare: Vec<(u32, u32)>,
+    costs: Vec<u32>,
+    last: u32,
+}
+
+impl RadixHeap {
+    fn bucket(&self, cost: u32) -> usize {
+        32 - (cost ^ self.last).leading_zeros() as usize
+    }
+}
+
+impl PriorityQueue<u32> for RadixHeap {
+    fn new(node_count: usize) -> Self {
+        Self {
+            buckets: vec![Vec::new(); 33],
+            spare: Vec::new(),
+            costs: vec![u32::MAX; node_count],
+            last: 0,
+        }
+    }
+
+    fn push(&mut self, node: usize, cost: u32) {
+        assert!(cost >= self.last, "Radix heap costs must not decrease");
+        if cost < self.costs[node] {
+            self.costs[node] = cost;
+            let bucket = self.bucket(cost);
+            self.buckets[bucket].push((cost, node as u32));
+        }
+    }
+
+    fn pop(&mut self) -> Option<(usize, u32)> {
+        loop {
+            if let Some((cost, node)) = self.buckets[0].pop() {
+                if self.costs[node as usize] != cost {
+                    continue;
+                }
+                self.costs[node as usize] = u32::MAX;
+                return Some((node as usize, cost));
+            }
+
+            // Refill bucket 0 from the first non-empty bucket, dropping stale entries
+            let i = (1..self.buckets.len()).find(|i| !self.buckets[*i].is_empty())?;
+            let mut entries = std::mem::take(&mut self.spare);
+            std::mem::swap(&mut entries, &mut self.buckets[i]);
+            entries.retain(|(cost, node)| self.costs[*node as usize] == *cost);
+            if let Some(&(min, _)) = entries.iter().min() {
+                self.last = min;
+            }
+            for (cost, node) in entries.drain(..) {
+                let bucket = self.bucket(cost);
+                self.buckets[bucket].push((cost, node));
+            }
+            self.spare = entries;
+        }
+    }
+
+    fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            for (_, node) in bucket.drain(..) {
+                self.costs[node as usize] = u32::MAX;
+            }
+        }
+        self.last = 0;
+    }
+}
+
+/// Queue choice for the full searches behind landmark creation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueKind {
+    Binary,
+    Quad,
+    Radix,
+}
+
+impl QueueKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "binary" => Some(Self::Binary),
+            "quad" => Some(Self::Quad),
+            "radix" => Some(Self::Radix),
+            _ => None,
+        }
+    }
+}
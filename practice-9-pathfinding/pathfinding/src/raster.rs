@@ -0,0 +1,154 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::ControlFlow;
+
+use crate::geo::BoundingBox;
+use crate::graph::{Graph, Search};
+use crate::queue::DefaultQueue;
+use crate::{Map, UNREACHED};
+
+/// Written for cells without any reached node in ASCII grids.
+const NO_DATA: i32 = -9999;
+
+/// Drive time from the closest of the sources to every node, like `full_dijkstra` with all the
+/// sources at distance 0. Run it on the `Reversed` map for the drive time to the closest source.
+pub fn multi_source_dijkstra(graph: &impl Graph, sources: &[u32]) -> Vec<u32> {
+    let sources: Vec<usize> = sources.iter().map(|s| *s as usize).collect();
+    let mut search = Search::<DefaultQueue>::new(graph.node_count());
+    search.run(graph, &sources, |_| 0, |_, _| ControlFlow::Continue(()));
+    search.into_tree().distances
+}
+
+/// Lowest drive time of the nodes in every cell of a latitude/longitude grid, in centiseconds.
+/// Row 0 is the northernmost row, like in images.
+pub struct TravelTimeRaster {
+    pub bounds: BoundingBox,
+    /// Width and height of a cell in degrees.
+    pub cell_size: f64,
+    pub rows: usize,
+    pub columns: usize,
+    pub cells: Vec<Option<u32>>,
+}
+
+impl TravelTimeRaster {
+    /// Rasterises the reached nodes inside `bounds`, or inside the box around every reached
+    /// node if it is None.
+    pub fn new(
+        map: &Map,
+        distances: &[u32],
+        bounds: Option<BoundingBox>,
+        cell_size: f64,
+    ) -> Option<Self> {
+        let bounds = match bounds {
+            Some(bounds) => bounds,
+            None => {
+                let reached: Vec<u32> = (0..distances.len() as u32)
+                    .filter(|n| distances[*n as usize] < UNREACHED)
+                    .collect();
+                BoundingBox::around(map, &reached, 0.0)?
+            }
+        };
+        let rows = ((bounds.max_latitude - bounds.min_latitude) / cell_size).ceil() as usize;
+        let columns = ((bounds.max_longitude - bounds.min_longitude) / cell_size).ceil() as usize;
+        let mut raster = Self {
+            bounds,
+            cell_size,
+            rows: rows.max(1),
+            columns: columns.max(1),
+            cells: vec![None; rows.max(1) * columns.max(1)],
+        };
+
+        for (node, &distance) in distances.iter().enumerate() {
+            let (latitude, longitude) = map.get_coordinates_from_node(node);
+            if distance >= UNREACHED || !bounds.contains(latitude, longitude) {
+                continue;
+            }
+            let row = ((bounds.max_latitude - latitude) / cell_size) as usize;
+            let column = ((longitude - bounds.min_longitude) / cell_size) as usize;
+            let cell = &mut raster.cells[row.min(raster.rows - 1) * raster.columns
+                + column.min(raster.columns - 1)];
+            *cell = Some(cell.map_or(distance, |c| c.min(distance)));
+        }
+        Some(raster)
+    }
+
+    /// Highest drive time in any cell.
+    pub fn max_time(&self) -> u32 {
+        self.cells.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    /// ESRI world file for the images: cell size, two rotation terms, negative cell size, then
+    /// the longitude and latitude of the centre of the top left cell.
+    pub fn write_world_file(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", self.cell_size)?;
+        writeln!(writer, "0.0")?;
+        writeln!(writer, "0.0")?;
+        writeln!(writer, "{}", -self.cell_size)?;
+        writeln!(writer, "{}", self.bounds.min_longitude + self.cell_size / 2.0)?;
+        writeln!(writer, "{}", self.bounds.max_latitude - self.cell_size / 2.0)?;
+        writer.flush()
+    }
+
+    /// Binary greyscale image, black for no drive time and lighter up to `max_time`. Cells
+    /// without any node and cells above `max_time` are white.
+    pub fn write_pgm(&self, path: &str, max_time: u32) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, "P5\n{} {}\n255\n", self.columns, self.rows)?;
+        for cell in &self.cells {
+            let shade = match cell {
+                Some(time) if *time <= max_time => {
+                    (*time as u64 * 254 / max_time.max(1) as u64) as u8
+                }
+                _ => 255,
+            };
+            writer.write_all(&[shade])?;
+        }
+        writer.flush()
+    }
+
+    /// Binary colour image from green through yellow to red at `max_time`. Cells without any
+    /// node and cells above `max_time` are white.
+    pub fn write_ppm(&self, path: &str, max_time: u32) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, "P6\n{} {}\n255\n", self.columns, self.rows)?;
+        for cell in &self.cells {
+            let colour = match cell {
+                Some(time) if *time <= max_time => {
+                    let t = *time as f64 / max_time.max(1) as f64;
+                    [
+                        (510.0 * t).min(255.0) as u8,
+                        (510.0 * (1.0 - t)).min(200.0) as u8,
+                        40,
+                    ]
+                }
+                _ => [255, 255, 255],
+            };
+            writer.write_all(&colour)?;
+        }
+        writer.flush()
+    }
+
+    /// ESRI ASCII grid with drive times in minutes, which GIS tools read with its location.
+    pub fn write_ascii_grid(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "ncols {}", self.columns)?;
+        writeln!(writer, "nrows {}", self.rows)?;
+        writeln!(writer, "xllcorner {}", self.bounds.min_longitude)?;
+        let lowest = self.bounds.max_latitude - self.rows as f64 * self.cell_size;
+        writeln!(writer, "yllcorner {}", lowest)?;
+        writeln!(writer, "cellsize {}", self.cell_size)?;
+        writeln!(writer, "NODATA_value {}", NO_DATA)?;
+        for row in self.cells.chunks(self.columns) {
+            let values: Vec<String> = row
+                .iter()
+                .map(|cell| match cell {
+                    Some(time) => format!("{:.2}", *time as f64 / 6000.0),
+                    None => NO_DATA.to_string(),
+                })
+                .collect();
+            writeln!(writer, "{}", values.join(" "))?;
+        }
+        writer.flush()
+    }
+}
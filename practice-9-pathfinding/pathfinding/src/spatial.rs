@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use crate::geo::haversine_distance;
+use crate::Map;
+
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Uniform latitude/longitude grid over the nodes of a map, for finding the nodes near a point.
+///
+/// Cells are `cell_size` meters high and at least that wide at the map's highest latitude, so a
+/// query looks at a few more cells further south instead of missing nodes further north.
+pub struct GridIndex {
+    cell_latitude: f64,
+    cell_longitude: f64,
+    cells: HashMap<(i32, i32), Vec<u32>>,
+}
+
+impl GridIndex {
+    pub fn new(map: &Map, cell_size: f64) -> Self {
+        let highest = map
+            .nodes
+            .iter()
+            .map(|n| n.latitude.abs())
+            .fold(0.0, f64::max)
+            .min(85.0);
+        let cell_latitude = cell_size / METERS_PER_DEGREE;
+        let mut index = Self {
+            cell_latitude,
+            cell_longitude: cell_latitude / highest.to_radians().cos(),
+            cells: HashMap::new(),
+        };
+        for (i, node) in map.nodes.iter().enumerate() {
+            let cell = index.cell(node.latitude, node.longitude);
+            index.cells.entry(cell).or_default().push(i as u32);
+        }
+        index
+    }
+
+    fn cell(&self, latitude: f64, longitude: f64) -> (i32, i32) {
+        (
+            (latitude / self.cell_latitude).floor() as i32,
+            (longitude / self.cell_longitude).floor() as i32,
+        )
+    }
+
+    /// Nodes within `radius` meters of the point as (node, distance in meters), closest first.
+    pub fn nodes_within(
+        &self,
+        map: &Map,
+        latitude: f64,
+        longitude: f64,
+        radius: f64,
+    ) -> Vec<(u32, f64)> {
+        let (row, column) = self.cell(latitude, longitude);
+        let rows = (radius / METERS_PER_DEGREE / self.cell_latitude).ceil() as i32;
+        let meters_per_longitude = METERS_PER_DEGREE * latitude.to_radians().cos();
+        let columns = (radius / meters_per_longitude / self.cell_longitude).ceil() as i32;
+
+        let mut found = Vec::new();
+        for r in row - rows..=row + rows {
+            for c in column - columns..=column + columns {
+                for &node in self.cells.get(&(r, c)).into_iter().flatten() {
+                    let (node_latitude, node_longitude) =
+                        map.get_coordinates_from_node(node as usize);
+                    let distance =
+                        haversine_distance(latitude, longitude, node_latitude, node_longitude);
+                    if distance <= radius {
+                        found.push((node, distance));
+                    }
+                }
+            }
+        }
+        found.sort_unstable_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        found
+    }
+}
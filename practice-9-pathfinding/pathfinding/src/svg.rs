@@ -0,0 +1,251 @@
+use std::cmp::Reverse;
+use std::f64::consts::FRAC_PI_4;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+
+use crate::geo::BoundingBox;
+use crate::Map;
+
+/// Edges drawn at most, roads with the lowest speed limits are left out above it.
+const MAX_ROAD_EDGES: usize = 200_000;
+/// Speed limit from which roads are drawn thicker.
+const FAST_ROAD: u16 = 80;
+/// Visited nodes are coloured in this many steps from the first settled to the last.
+const VISITED_STEPS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+    /// Longitude scaled by the cosine of the middle latitude, true to scale near the middle.
+    Equirectangular,
+    /// Web Mercator like map tiles, true to angles everywhere.
+    WebMercator,
+}
+
+impl Projection {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "equirectangular" => Some(Self::Equirectangular),
+            "mercator" => Some(Self::WebMercator),
+            _ => None,
+        }
+    }
+}
+
+/// SVG drawing of part of a map. Layers are drawn in the order they are added, so add the road
+/// network first and the path last.
+pub struct SvgMap<'a> {
+    map: &'a Map,
+    bounds: BoundingBox,
+    projection: Projection,
+    /// Pixels per projected unit, and the projected top left corner.
+    scale: f64,
+    origin: (f64, f64),
+    width: f64,
+    height: f64,
+    body: String,
+    legend: Vec<(String, &'static str)>,
+}
+
+impl<'a> SvgMap<'a> {
+    pub fn new(map: &'a Map, bounds: BoundingBox, projection: Projection, width: u32) -> Self {
+        let mut svg = Self {
+            map,
+            bounds,
+            projection,
+            scale: 1.0,
+            origin: (0.0, 0.0),
+            width: width as f64,
+            height: 0.0,
+            body: String::new(),
+            legend: Vec::new(),
+        };
+        let (left, top) = svg.project_raw(bounds.max_latitude, bounds.min_longitude);
+        let (right, bottom) = svg.project_raw(bounds.min_latitude, bounds.max_longitude);
+        svg.scale = svg.width / (right - left);
+        svg.origin = (left, top);
+        svg.height = ((bottom - top) * svg.scale).ceil();
+        svg
+    }
+
+    /// Projected coordinates with y growing southwards.
+    fn project_raw(&self, latitude: f64, longitude: f64) -> (f64, f64) {
+        match self.projection {
+            Projection::Equirectangular => {
+                let middle = (self.bounds.min_latitude + self.bounds.max_latitude) / 2.0;
+                (longitude * middle.to_radians().cos(), -latitude)
+            }
+            Projection::WebMercator => (
+                longitude.to_radians(),
+                -(FRAC_PI_4 + latitude.to_radians() / 2.0).tan().ln(),
+            ),
+        }
+    }
+
+    fn point(&self, node: u32) -> (f64, f64) {
+        let (latitude, longitude) = self.map.get_coordinates_from_node(node as usize);
+        let (x, y) = self.project_raw(latitude, longitude);
+        (
+            (x - self.origin.0) * self.scale,
+            (y - self.origin.1) * self.scale,
+        )
+    }
+
+    fn inside(&self, node: u32) -> bool {
+        let (latitude, longitude) = self.map.get_coordinates_from_node(node as usize);
+        self.bounds.contains(latitude, longitude)
+    }
+
+    /// One `<path>` of round dots, much smaller than a `<circle>` per node.
+    fn dots(&mut self, nodes: impl Iterator<Item = u32>, colour: &str, size: f64) {
+        let mut data = String::new();
+        for node in nodes.filter(|n| self.inside(*n)) {
+            let (x, y) = self.point(node);
+            let _ = write!(data, "M{:.1} {:.1}h0", x, y);
+        }
+        if !data.is_empty() {
+            let _ = writeln!(
+                self.body,
+                r#"<path d="{}" stroke="{}" stroke-width="{}" stroke-linecap="round"/>"#,
+                data, colour, size
+            );
+        }
+    }
+
+    /// Roads with at least one end in the box, two-way roads once. Faster roads are drawn
+    /// thicker. If the box holds too many edges, the slowest roads are left out.
+    pub fn draw_road_network(&mut self) -> &mut Self {
+        let mut roads: Vec<(u16, u32, u32)> = Vec::new();
+        for (from, edges) in self.map.edges.iter().enumerate() {
+            let from = from as u32;
+            for edge in edges {
+                let twin_drawn = edge.to < from
+                    && self.map.edges[edge.to as usize]
+                        .iter()
+                        .any(|e| e.to == from);
+                if !twin_drawn && (self.inside(from) || self.inside(edge.to)) {
+                    roads.push((edge.speed_limit, from, edge.to));
+                }
+            }
+        }
+        roads.sort_unstable_by_key(|r| Reverse(r.0));
+        roads.truncate(MAX_ROAD_EDGES);
+
+        for (fast, width, colour) in [(false, 0.5, "#c8c8c8"), (true, 1.2, "#999999")] {
+            let mut data = String::new();
+            for &(_, from, to) in roads.iter().filter(|r| (r.0 >= FAST_ROAD) == fast) {
+                let ((x1, y1), (x2, y2)) = (self.point(from), self.point(to));
+                let _ = write!(data, "M{:.1} {:.1}L{:.1} {:.1}", x1, y1, x2, y2);
+            }
+            if data.is_empty() {
+                continue;
+            }
+            let _ = writeln!(
+                self.body,
+                r#"<path d="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+                data, colour, width
+            );
+        }
+        self
+    }
+
+    /// Nodes in the order a search settled them, from blue for the first to red for the last.
+    pub fn draw_visited(&mut self, visited: &[u32]) -> &mut Self {
+        let step = visited.len().div_ceil(VISITED_STEPS).max(1);
+        for (i, chunk) in visited.chunks(step).enumerate() {
+            let t = i as f64 / (VISITED_STEPS - 1) as f64;
+            let colour = format!(
+                "rgb({},{},{})",
+                (40.0 + 215.0 * t) as u8,
+                (90.0 + 60.0 * (1.0 - (2.0 * t - 1.0).abs())) as u8,
+                (255.0 - 215.0 * t) as u8
+            );
+            self.dots(chunk.iter().copied(), &colour, 2.5);
+        }
+        self.legend.push((
+            format!("Settled nodes, blue first, red last ({})", visited.len()),
+            "rgb(150,120,150)",
+        ));
+        self
+    }
+
+    /// Path with the source included.
+    pub fn draw_path(&mut self, path: &[u32], colour: &'static str, label: &str) -> &mut Self {
+        let mut data = String::new();
+        for (i, node) in path.iter().enumerate() {
+            let (x, y) = self.point(*node);
+            let _ = write!(data, "{}{:.1} {:.1}", if i == 0 { "M" } else { "L" }, x, y);
+        }
+        let _ = writeln!(
+            self.body,
+            r#"<path d="{}" fill="none" stroke="{}" stroke-width="3" stroke-linejoin="round"/>"#,
+            data, colour
+        );
+        self.dots(path.first().into_iter().copied(), "#1a9641", 10.0);
+        self.dots(path.last().into_iter().copied(), "#d7191c", 10.0);
+        self.legend.push((label.to_owned(), colour));
+        self
+    }
+
+    pub fn draw_landmarks(&mut self, landmarks: &[u32]) -> &mut Self {
+        self.dots(landmarks.iter().copied(), "#7b3294", 12.0);
+        self.legend.push(("Landmarks".to_owned(), "#7b3294"));
+        self
+    }
+
+    /// Points of interest coloured by their lowest category bit.
+    pub fn draw_points_of_interest(&mut self) -> &mut Self {
+        const CATEGORIES: [(u8, &str, &str); 5] = [
+            (1, "Places", "#666666"),
+            (2, "Gas stations", "#e66101"),
+            (4, "Charging stations", "#0571b0"),
+            (8, "Places to eat", "#ca0020"),
+            (16, "Places to drink", "#5e3c99"),
+        ];
+        for (bit, name, colour) in CATEGORIES {
+            let nodes: Vec<u32> = self
+                .map
+                .points_of_interest
+                .iter()
+                .filter(|(_, (category, _))| category & (2 * bit - 1) == bit)
+                .map(|(node, _)| *node)
+                .collect();
+            if nodes.iter().any(|n| self.inside(*n)) {
+                self.dots(nodes.into_iter(), colour, 5.0);
+                self.legend.push((name.to_owned(), colour));
+            }
+        }
+        self
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        svg.push_str(r#"<svg xmlns="http://www.w3.org/2000/svg" "#);
+        let _ = writeln!(
+            svg,
+            r#"width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = self.width,
+            h = self.height
+        );
+        let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+        svg.push_str(&self.body);
+        let _ = writeln!(svg, r#"<g font-family="sans-serif" font-size="13">"#);
+        for (i, (label, colour)) in self.legend.iter().enumerate() {
+            let y = 20.0 + 18.0 * i as f64;
+            let label = label.replace('&', "&amp;").replace('<', "&lt;");
+            let _ = writeln!(
+                svg,
+                r#"<rect x="10" y="{}" width="12" height="12" fill="{}"/>"#,
+                y - 10.0,
+                colour
+            );
+            let _ = writeln!(svg, r#"<text x="28" y="{}">{}</text>"#, y, label);
+        }
+        svg.push_str("</g>\n</svg>\n");
+        svg
+    }
+
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+}
@@ -0,0 +1,1325 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::ops::ControlFlow;
+
+use crate::arc_flags::{arc_flags_dijkstra, ArcFlags};
+use crate::astar::{astar, StraightLine};
+use crate::benchmark::Random;
+use crate::bidirectional::bidirectional_dijkstra;
+use crate::components::strongly_connected_components;
+use crate::detour::detours;
+use crate::directions::{turn_by_turn, Maneuver, Route};
+use crate::duration::{Language, Schedule, Timestamp, TravelDuration};
+use crate::ev::{ev_route, ChargingModel};
+use crate::extract::write_map_to_paths;
+use crate::geo::{haversine_distance, BoundingBox, Polygon};
+use crate::graph::{point_to_point, Filtered, ProfileWeighted, Reversed, Search};
+use crate::hub_labels::{HubLabels, HubOrder};
+use crate::matching::{match_trace, MatchingParameters};
+use crate::matrix::travel_time_matrix;
+use crate::meeting::{meeting_point, Objective};
+use crate::osm::import_osm;
+use crate::overlay::{
+    alt_with_overlay, closest_dijkstra_with_overlay, Overlay, MAX_PENALIZED_DRIVE_TIME,
+};
+use crate::profile::VehicleProfile;
+use crate::queue::{QuadHeap, QueueKind};
+use crate::raster::{multi_source_dijkstra, TravelTimeRaster};
+use crate::spatial::GridIndex;
+use crate::svg::{Projection, SvgMap};
+use crate::validate::MapReport;
+use crate::waypoints::{farthest_landmarks, Landmarks, Waypoints};
+use crate::{
+    alt, category_based_dijkstra, centi_seconds_to_time_format, closest_dijkstra, full_dijkstra,
+    get_map_from_paths, get_waypoints, map_paths, path_from_previous, EdgeTo, Map, Node, UNREACHED,
+};
+const FIXTURE_PREFIX: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/tiny");
+
+/// Random directed map with coordinates around Trondheim, drive times from length and speed
+/// limit like the real data, and about one node in five as a point of interest.
+fn random_map(random: &mut Random, node_count: usize, edge_count: usize) -> Map {
+    let nodes: Vec<Node> = (0..node_count)
+        .map(|i| {
+            let latitude = 63.0 + random.below(100_000) as f64 / 100_000.0;
+            let longitude = 10.0 + random.below(200_000) as f64 / 100_000.0;
+            Node::new(i as u32, latitude, longitude)
+        })
+        .collect();
+    let mut edges = vec![Vec::new(); node_count];
+    for _ in 0..edge_count {
+        let from = random.below(node_count);
+        let to = random.below(node_count) as u32;
+        let length = 50 + random.below(3000) as u32;
+        let speed_limit = [30, 50, 60, 80, 90, 110][random.below(6)];
+        let drive_time = length * 360 / speed_limit as u32;
+        edges[from].push(EdgeTo::new(to, drive_time, length, speed_limit));
+    }
+    let mut points_of_interest = HashMap::new();
+    for node in 0..node_count as u32 {
+        if random.below(5) == 0 {
+            let category = 1 << random.below(6);
+            points_of_interest.insert(node, (category, format!("Poi {}", node)));
+        }
+    }
+    Map::from_nodes_edges_and_poi(nodes, edges, points_of_interest)
+}
+
+fn waypoints_for(map: &Map, sources: &[u32], quantised: bool) -> Waypoints {
+    let reverse_map = map.get_reverse_copy();
+    let mut bytes = Waypoints::header_bytes(sources.len(), map.edges.len(), quantised);
+    for &source in sources {
+        bytes.extend(Waypoints::landmark_bytes(
+            source,
+            &full_dijkstra(map, source).distances,
+            &full_dijkstra(&reverse_map, source).distances,
+            quantised,
+        ));
+    }
+    Waypoints::from_bytes(&bytes).expect("Could not read waypoints")
+}
+
+/// Checks that `path` starts at `source`, follows edges of the map and that the cheapest edges
+/// between its nodes add up to `distance`.
+fn assert_valid_path(map: &Map, source: usize, path: &[u32], distance: usize) {
+    assert_eq!(path.first(), Some(&(source as u32)));
+    let mut total = 0;
+    for pair in path.windows(2) {
+        let edge = map.edges[pair[0] as usize]
+            .iter()
+            .filter(|e| e.to == pair[1])
+            .min_by_key(|e| e.drive_time);
+        total += edge
+            .unwrap_or_else(|| panic!("No edge from {} to {}", pair[0], pair[1]))
+            .drive_time as usize;
+    }
+    assert_eq!(total, distance);
+}
+
+#[test]
+fn searches_agree_on_random_maps() {
+    let mut random = Random::new(2101);
+    for _ in 0..20 {
+        let node_count = 50 + random.below(400);
+        let edge_count = node_count * (1 + random.below(4));
+        let map = random_map(&mut random, node_count, edge_count);
+        let sources: Vec<u32> = (0..3).map(|_| random.below(node_count) as u32).collect();
+        let exact = waypoints_for(&map, &sources, false);
+        let quantised = waypoints_for(&map, &sources, true);
+        let bound = StraightLine::new(&map);
+        let reverse = Reversed::new(&map);
+
+        for _ in 0..5 {
+            let source = random.below(node_count);
+            let tree = full_dijkstra(&map, source as u32);
+            for goal in 0..node_count {
+                let Some(distance) = tree.distance_to(goal) else {
+                    assert_eq!(tree.path_to(goal), None);
+                    assert_eq!(closest_dijkstra(&map, source, goal), None);
+                    assert_eq!(astar(&map, &bound, source, goal), None);
+                    assert_eq!(bidirectional_dijkstra(&map, &reverse, source, goal), None);
+                    continue;
+                };
+                let full_path = tree.path_to(goal).unwrap();
+                assert_valid_path(&map, source, &full_path, distance as usize);
+
+                let result = closest_dijkstra(&map, source, goal).unwrap();
+                assert_eq!(result.drive_time, distance);
+                assert_eq!(result.visited.last(), Some(&(goal as u32)));
+                assert_valid_path(&map, source, &result.path, distance as usize);
+
+                for waypoints in [&exact, &quantised] {
+                    assert!(waypoints.lower_bound(source, goal) <= distance);
+                    let result = alt(&map, waypoints, source, goal).unwrap();
+                    assert_eq!(result.drive_time, distance);
+                    assert_valid_path(&map, source, &result.path, distance as usize);
+                }
+
+                assert!(bound.lower_bound(source, goal) <= distance);
+                let result = astar(&map, &bound, source, goal).unwrap();
+                assert_eq!(result.drive_time, distance);
+                assert_valid_path(&map, source, &result.path, distance as usize);
+                let result = bidirectional_dijkstra(&map, &reverse, source, goal).unwrap();
+                assert_eq!(result.drive_time, distance);
+                assert_eq!(result.path.last(), Some(&(goal as u32)));
+                assert_valid_path(&map, source, &result.path, distance as usize);
+            }
+        }
+    }
+}
+
+#[test]
+fn category_search_returns_closest_first() {
+    let mut random = Random::new(8);
+    for _ in 0..20 {
+        let node_count = 50 + random.below(400);
+        let map = random_map(&mut random, node_count, node_count * 3);
+        let source = random.below(node_count);
+        let category = 1 << random.below(6);
+        let amount = 1 + random.below(10) as u32;
+        let distances = full_dijkstra(&map, source as u32).distances;
+
+        let results = category_based_dijkstra(&map, source, category, amount);
+        assert!(results.len() <= amount as usize);
+        for pair in results.windows(2) {
+            assert!(distances[pair[0] as usize] <= distances[pair[1] as usize]);
+        }
+        for node in &results {
+            assert_eq!(map.points_of_interest[node].0 & category, category);
+        }
+
+        // Nothing of the category that was left out may be closer than the last result
+        let reachable = map
+            .points_of_interest
+            .iter()
+            .filter(|(node, poi)| {
+                poi.0 & category == category && distances[**node as usize] < UNREACHED
+            })
+            .count();
+        assert_eq!(results.len(), reachable.min(amount as usize));
+        if let Some(last) = results.last() {
+            for (node, poi) in &map.points_of_interest {
+                if poi.0 & category == category && !results.contains(node) {
+                    assert!(distances[*node as usize] >= distances[*last as usize]);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn overlay_searches_match_edited_map() {
+    let mut random = Random::new(26);
+    for _ in 0..10 {
+        let node_count = 50 + random.below(300);
+        let map = random_map(&mut random, node_count, node_count * 3);
+        let sources: Vec<u32> = (0..3).map(|_| random.below(node_count) as u32).collect();
+        let waypoints = waypoints_for(&map, &sources, false);
+
+        // The same closures and penalties made by hand on a copy of the map
+        let mut overlay = Overlay::new();
+        let mut edited = map.clone();
+        let zone = Polygon::from_bounding_box(63.3, 10.5, 63.5, 11.0);
+        overlay.avoid_zone(zone.clone());
+        for _ in 0..3 {
+            let node = random.below(node_count) as u32;
+            overlay.block_node(node);
+            for edges in &mut edited.edges {
+                edges.retain(|e| e.to != node);
+            }
+        }
+        for edges in &mut edited.edges {
+            edges.retain(|e| {
+                let to = &map.nodes[e.to as usize];
+                !zone.contains(to.latitude, to.longitude)
+            });
+        }
+        for _ in 0..node_count / 10 {
+            let from = random.below(node_count);
+            let Some(edge) = map.edges[from].first() else {
+                continue;
+            };
+            let to = edge.to;
+            if random.below(2) == 0 {
+                overlay.block_edge(from as u32, to);
+                edited.edges[from].retain(|e| e.to != to);
+            } else {
+                let factor = 1.0 + random.below(40) as f64 / 10.0;
+                overlay.penalize_edge(from as u32, to, factor);
+                for edge in edited.edges[from].iter_mut().filter(|e| e.to == to) {
+                    edge.drive_time = (edge.drive_time as f64 * factor).ceil() as u32;
+                }
+            }
+        }
+        let applied = overlay.apply(&map);
+
+        for _ in 0..3 {
+            let source = random.below(node_count);
+            let tree = full_dijkstra(&edited, source as u32);
+            for goal in 0..node_count {
+                let result = closest_dijkstra_with_overlay(&map, &applied, source, goal);
+                let alt_result = alt_with_overlay(&map, &waypoints, &applied, source, goal);
+                let Some(distance) = tree.distance_to(goal) else {
+                    assert_eq!(result, None);
+                    assert_eq!(alt_result, None);
+                    continue;
+                };
+                for result in [result.unwrap(), alt_result.unwrap()] {
+                    assert_eq!(result.drive_time, distance);
+                    assert_valid_path(&edited, source, &result.path, distance as usize);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn huge_penalty_avoids_the_road_without_closing_it() {
+    let map = crossing(50);
+    let mut overlay = Overlay::new();
+    overlay
+        .penalize_edge(0, 1, 1e30)
+        .penalize_edge(1, 2, 1e30)
+        .penalize_edge(3, 1, 1e4);
+    let applied = overlay.apply(&map);
+    assert_eq!(
+        applied.edge_cost(0, &map.edges[0][0]),
+        Some(MAX_PENALIZED_DRIVE_TIME)
+    );
+
+    // Two capped edges in a row are still a route, if nothing else is
+    let result = closest_dijkstra_with_overlay(&map, &applied, 0, 2).unwrap();
+    assert_eq!(result.drive_time, 2 * MAX_PENALIZED_DRIVE_TIME);
+    let result = closest_dijkstra_with_overlay(&map, &applied, 3, 4).unwrap();
+    assert_eq!(result.drive_time, 7200 * 10_000 + 7200);
+}
+
+#[test]
+fn huge_edge_weights_do_not_overflow() {
+    // 0 -> 1 -> 2 would wrap around, 0 -> 3 -> 4 would end past UNREACHED
+    let mut map = map_from_edges(5, &[(0, 1), (1, 2), (0, 3), (3, 4)]);
+    map.edges[1][0].drive_time = u32::MAX;
+    map.edges[0][1].drive_time = UNREACHED - 1;
+
+    let distances = full_dijkstra(&map, 0).distances;
+    assert_eq!(distances, [0, 7200, UNREACHED, UNREACHED - 1, UNREACHED]);
+    assert_eq!(closest_dijkstra(&map, 0, 2), None);
+    assert_eq!(closest_dijkstra(&map, 0, 4), None);
+    let result = closest_dijkstra(&map, 0, 3).unwrap();
+    assert_eq!(result.drive_time, UNREACHED - 1);
+
+    let arc_flags = ArcFlags::build(&map, 2, 2, 1, |_, _| {});
+    for goal in [2, 4] {
+        assert_eq!(arc_flags_dijkstra(&map, &arc_flags, 0, goal), None);
+    }
+    // With 3 as the first hub, 0 -> 4 is only found as the sum of two label entries
+    for order in [HubOrder::coverage().nodes(&map), vec![3, 0, 1, 2, 4]] {
+        let labels = HubLabels::build(&map, &order, |_| {});
+        for goal in [2, 4] {
+            assert!(labels.query(0, goal).is_none());
+        }
+        assert_eq!(labels.query(0, 3).unwrap().drive_time, UNREACHED - 1);
+    }
+}
+
+#[test]
+fn loads_fixture_map() {
+    let map = Map::load(FIXTURE_PREFIX).unwrap();
+
+    assert_eq!(map.nodes.len(), 6);
+    assert_eq!(map.get_coordinates_from_node(3), (63.4250, 10.3900));
+    assert_eq!(map.edges.iter().map(Vec::len).sum::<usize>(), 9);
+    assert_eq!(map.edges[1][1], EdgeTo::new(2, 3600, 900, 90));
+    assert!(map.edges[5].is_empty());
+    assert_eq!(map.points_of_interest.len(), 4);
+    assert_eq!(map.get_name(3), "Lade ladestasjon og kafé");
+    assert_eq!(
+        map.points_of_interest[&5],
+        (24, "Kaffebar & pizza".to_owned())
+    );
+
+    let tree = full_dijkstra(&map, 0);
+    assert_eq!(tree.distances, vec![0, 5760, 9360, 2880, 16560, 20160]);
+    assert_eq!(tree.path_to(5), Some(vec![0, 1, 2, 4, 5]));
+    assert_eq!(path_from_previous(&tree.previous, 5, 0), None);
+    assert_eq!(category_based_dijkstra(&map, 0, 8, 10), vec![3, 2, 5]);
+}
+
+#[test]
+fn matrix_matches_point_to_point() {
+    let mut random = Random::new(32);
+    let maps = [
+        Map::load(FIXTURE_PREFIX).unwrap(),
+        random_map(&mut random, 120, 200),
+    ];
+    for map in &maps {
+        let node_count = map.edges.len() as u32;
+        // The last node of the fixture has no edges out, and repeated ids are allowed
+        let mut sources: Vec<u32> = (0..node_count).rev().step_by(3).collect();
+        sources.push(sources[0]);
+        let mut targets: Vec<u32> = (0..node_count).step_by(2).collect();
+        targets.push(targets[0]);
+
+        for threads in [1, 4] {
+            let matrix = travel_time_matrix(map, &sources, &targets, threads);
+            assert_eq!(matrix.times.len(), sources.len() * targets.len());
+            let mut unreachable = 0;
+            for (row, source) in sources.iter().enumerate() {
+                for (column, target) in targets.iter().enumerate() {
+                    let expected = point_to_point::<QuadHeap<u32>>(
+                        map,
+                        *source as usize,
+                        *target as usize,
+                        |_| 0,
+                    );
+                    assert_eq!(matrix.get(row, column), expected.map(|r| r.drive_time));
+                    unreachable += usize::from(matrix.get(row, column).is_none());
+                }
+            }
+            assert!(unreachable > 0);
+        }
+    }
+}
+
+#[test]
+fn written_map_loads_unchanged() {
+    let map = Map::load(FIXTURE_PREFIX).unwrap();
+
+    let prefix = env::temp_dir()
+        .join(format!("pathfinding_round_trip_{}", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let (node_copy, edge_copy, poi_copy) = map_paths(&prefix);
+    write_map_to_paths(&map, &node_copy, &edge_copy, &poi_copy).unwrap();
+    let copy = Map::load(&prefix).unwrap();
+    for path in [&node_copy, &edge_copy, &poi_copy] {
+        let _ = fs::remove_file(path);
+    }
+
+    assert_eq!(copy.edges, map.edges);
+    assert_eq!(copy.points_of_interest, map.points_of_interest);
+    for (a, b) in copy.nodes.iter().zip(&map.nodes) {
+        assert_eq!(
+            (a.id, a.latitude, a.longitude),
+            (b.id, b.latitude, b.longitude)
+        );
+    }
+}
+
+#[test]
+fn region_copy_round_trips_with_remapped_ids() {
+    let mut random = Random::new(30);
+    let map = random_map(&mut random, 400, 1600);
+    let region = Polygon::from_bounding_box(63.2, 10.4, 63.7, 11.3);
+    let inside = |node: u32| {
+        let node = &map.nodes[node as usize];
+        region.contains(node.latitude, node.longitude)
+    };
+    let (region_map, original_ids) = map.get_region_copy(&region);
+
+    let prefix = env::temp_dir()
+        .join(format!("pathfinding_region_{}", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let (node_path, edge_path, poi_path) = map_paths(&prefix);
+    write_map_to_paths(&region_map, &node_path, &edge_path, &poi_path).unwrap();
+    let copy = get_map_from_paths(&node_path, &edge_path, &poi_path).unwrap();
+    for path in [&node_path, &edge_path, &poi_path] {
+        let _ = fs::remove_file(path);
+    }
+
+    let kept: Vec<u32> = (0..400).filter(|n| inside(*n)).collect();
+    assert_eq!(original_ids, kept);
+    assert_eq!(copy.nodes.len(), kept.len());
+    for (i, node) in copy.nodes.iter().enumerate() {
+        let original = &map.nodes[original_ids[i] as usize];
+        assert_eq!(
+            (node.id, node.latitude, node.longitude),
+            (i as u32, original.latitude, original.longitude)
+        );
+    }
+
+    // Every edge between two kept nodes is kept, pointing at the new ids
+    let edge_count = |map: &Map| map.edges.iter().map(Vec::len).sum::<usize>();
+    let kept_edges = kept
+        .iter()
+        .flat_map(|n| &map.edges[*n as usize])
+        .filter(|e| inside(e.to))
+        .count();
+    assert_eq!(edge_count(&copy), kept_edges);
+    assert_eq!(copy.edges, region_map.edges);
+    for (from, edges) in copy.edges.iter().enumerate() {
+        for edge in edges {
+            let original_from = original_ids[from] as usize;
+            let original = EdgeTo {
+                to: original_ids[edge.to as usize],
+                ..edge.clone()
+            };
+            assert!(map.edges[original_from].contains(&original));
+        }
+    }
+
+    let kept_pois = map
+        .points_of_interest
+        .keys()
+        .filter(|n| inside(**n))
+        .count();
+    assert_eq!(copy.points_of_interest.len(), kept_pois);
+    assert_eq!(copy.points_of_interest, region_map.points_of_interest);
+    for (node, poi) in &copy.points_of_interest {
+        assert_eq!(&map.points_of_interest[&original_ids[*node as usize]], poi);
+    }
+}
+
+#[test]
+fn landmarks_are_chosen_from_the_loaded_map() {
+    let map = Map::load(FIXTURE_PREFIX).unwrap();
+    let path = env::temp_dir()
+        .join(format!("pathfinding_waypoints_{}.bin", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let farthest = Landmarks::farthest();
+    let waypoints = get_waypoints(&map, &path, &farthest, QueueKind::Quad, 2, false).unwrap();
+    assert_eq!(waypoints.node_count(), 6);
+    assert!(!waypoints.sources().is_empty());
+    assert!(waypoints.sources().iter().all(|s| *s < 6));
+    for goal in 0..6 {
+        assert_eq!(
+            alt(&map, &waypoints, 0, goal).map(|r| r.drive_time),
+            full_dijkstra(&map, 0).distance_to(goal)
+        );
+    }
+
+    // Listed landmarks replace a file made from others, and must be nodes of the map
+    let listed = Landmarks::from_name("4,1").unwrap();
+    let waypoints = get_waypoints(&map, &path, &listed, QueueKind::Quad, 2, false).unwrap();
+    assert_eq!(waypoints.sources(), [4, 1]);
+    for landmarks in [Landmarks::Nodes(vec![1, 6]), Landmarks::Nodes(Vec::new())] {
+        let result = get_waypoints(&map, &path, &landmarks, QueueKind::Quad, 2, false);
+        assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::InvalidInput));
+    }
+    assert_eq!(Landmarks::from_name("8"), Some(Landmarks::Farthest(8)));
+    assert_eq!(Landmarks::from_name("1,x"), None);
+    let _ = fs::remove_file(&path);
+
+    // On a grid the opposite corners are farthest apart, and the nodes on the other diagonal
+    // farthest from both
+    let grid = grid_map(6, 100.0);
+    let landmarks = farthest_landmarks(&grid, 4);
+    assert_eq!(landmarks.len(), 4);
+    assert_eq!(landmarks[..2], [35, 0]);
+    for landmark in &landmarks[2..] {
+        assert_eq!(landmark / 6 + landmark % 6, 5);
+    }
+}
+
+/// Road 0 -> 1 -> 2 with a fast 0 -> 1 of `fast_meters` and a slow way of 4 km through node 3.
+fn ev_road(fast_meters: u32, second_meters: u32) -> Map {
+    let nodes = (0..4)
+        .map(|i| Node::new(i, 63.0 + i as f64 / 100.0, 10.0))
+        .collect();
+    let edges = vec![
+        vec![
+            EdgeTo::new(1, 15000, fast_meters, 110),
+            EdgeTo::new(3, 10000, 2000, 50),
+        ],
+        vec![EdgeTo::new(2, 15000, second_meters, 80)],
+        Vec::new(),
+        vec![EdgeTo::new(1, 10000, 2000, 50)],
+    ];
+    Map::from_nodes_edges_and_poi(nodes, edges, HashMap::new())
+}
+
+#[test]
+fn ev_route_takes_shorter_way_when_fastest_runs_out() {
+    let map = ev_road(10000, 1000);
+    let charging = ChargingModel::new(1000, 200);
+
+    let route = ev_route(&map, 0, 2, 12000, 12000, &charging).unwrap();
+    assert_eq!(route.path, vec![0, 1, 2]);
+    assert_eq!(route.travel_time, 30000);
+    assert_eq!(route.arrival_charge, 1000);
+
+    // 11 km the fast way is out of range, 5 km through node 3 is not
+    let route = ev_route(&map, 0, 2, 10500, 10500, &charging).unwrap();
+    assert_eq!(route.path, vec![0, 3, 1, 2]);
+    assert_eq!(route.travel_time, 35000);
+    assert_eq!(route.arrival_charge, 5500);
+    assert!(route.chargers.is_empty());
+    assert!(ev_route(&map, 0, 2, 4000, 4000, &charging).is_none());
+}
+
+#[test]
+fn ev_route_stops_at_charger() {
+    let mut map = ev_road(5500, 5000);
+    assert!(ev_route(&map, 0, 2, 6000, 6000, &ChargingModel::new(1000, 200)).is_none());
+    map.points_of_interest.insert(1, (4, "Charger".to_owned()));
+
+    let route = ev_route(&map, 0, 2, 6000, 6000, &ChargingModel::new(1000, 200)).unwrap();
+    assert_eq!(route.path, vec![0, 1, 2]);
+    assert_eq!(route.chargers, vec![1]);
+    // Arrives at the charger with 0.5 km left and charges 5.5 km
+    assert_eq!(route.charging_time, 1000 + 200 * 55 / 10);
+    assert_eq!(route.travel_time, 15000 + route.charging_time + 15000);
+    assert_eq!(route.arrival_charge, 1000);
+
+    // When charging is slow, arriving with more charge the slow way is earlier overall
+    let route = ev_route(&map, 0, 2, 6000, 6000, &ChargingModel::new(1000, 10000)).unwrap();
+    assert_eq!(route.path, vec![0, 3, 1, 2]);
+    assert_eq!(route.chargers, vec![1]);
+    assert_eq!(route.charging_time, 1000 + 10000 * 4);
+    assert_eq!(route.travel_time, 20000 + route.charging_time + 15000);
+}
+
+/// Map with an edge from every (from, to) pair, 1 km at 50 km/h.
+fn map_from_edges(node_count: usize, pairs: &[(u32, u32)]) -> Map {
+    let nodes = (0..node_count as u32)
+        .map(|i| Node::new(i, 63.0 + i as f64 / 1000.0, 10.0))
+        .collect();
+    let mut edges = vec![Vec::new(); node_count];
+    for &(from, to) in pairs {
+        edges[from as usize].push(EdgeTo::new(to, 7200, 1000, 50));
+    }
+    Map::from_nodes_edges_and_poi(nodes, edges, HashMap::new())
+}
+
+#[test]
+fn components_split_cycles_joined_one_way() {
+    // Cycle 0-1-2 leads one way into cycle 3-4-5-6, node 7 has no edges
+    let cycles = [
+        (0, 1),
+        (1, 2),
+        (2, 0),
+        (2, 3),
+        (3, 4),
+        (4, 5),
+        (5, 6),
+        (6, 3),
+    ];
+    let mut map = map_from_edges(8, &cycles);
+    map.points_of_interest
+        .insert(0, (1, "Left behind".to_owned()));
+    map.points_of_interest.insert(5, (2, "Kept".to_owned()));
+
+    let (component, sizes) = strongly_connected_components(&map);
+    assert_eq!(sizes.len(), 3);
+    assert!(component[..3].iter().all(|c| *c == component[0]));
+    assert!(component[3..7].iter().all(|c| *c == component[3]));
+    assert_ne!(component[0], component[3]);
+    assert_ne!(component[7], component[0]);
+    assert_ne!(component[7], component[3]);
+    assert_eq!(sizes[component[0] as usize], 3);
+    assert_eq!(sizes[component[3] as usize], 4);
+    assert_eq!(sizes[component[7] as usize], 1);
+
+    let (largest, original_ids) = map.get_largest_component_copy();
+    assert_eq!(original_ids, vec![3, 4, 5, 6]);
+    for (i, node) in largest.nodes.iter().enumerate() {
+        assert_eq!(node.id, i as u32);
+        assert_eq!(node.latitude, map.nodes[original_ids[i] as usize].latitude);
+    }
+    let edges: Vec<Vec<u32>> = largest
+        .edges
+        .iter()
+        .map(|edges| edges.iter().map(|e| e.to).collect())
+        .collect();
+    assert_eq!(edges, vec![vec![1], vec![2], vec![3], vec![0]]);
+    assert_eq!(largest.points_of_interest.len(), 1);
+    assert_eq!(largest.points_of_interest[&2].1, "Kept");
+
+    // A cycle this long would overflow the stack of a recursive Tarjan
+    let length = 200_000;
+    let pairs: Vec<(u32, u32)> = (0..length).map(|i| (i, (i + 1) % length)).collect();
+    let (component, sizes) =
+        strongly_connected_components(&map_from_edges(length as usize, &pairs));
+    assert_eq!(sizes, vec![length as usize]);
+    assert!(component.iter().all(|c| *c == 0));
+}
+
+#[test]
+fn report_finds_pois_cut_off_from_main_network() {
+    // Main cycle 0-1-2-3, a cycle 4-5 reachable from it but with no way back, node 6 alone
+    let pairs = [(0, 1), (1, 2), (2, 3), (3, 0), (3, 4), (4, 5), (5, 4)];
+    let mut map = map_from_edges(7, &pairs);
+    for node in [1, 4, 6, 99] {
+        map.points_of_interest
+            .insert(node, (1, format!("Poi {}", node)));
+    }
+
+    let report = MapReport::new(&map);
+    assert_eq!(report.isolated_pois.count, 3);
+    assert_eq!(report.isolated_pois.examples, vec![4, 6, 99]);
+    assert_eq!(report.nodes_without_edges.examples, vec![6]);
+    let text = report.to_text();
+    let heading = "Points of interest outside the largest strongly connected component";
+    assert!(text.contains(&format!("{}: 3\n  e.g. 4, 6, 99\n", heading)));
+    assert!(report
+        .to_json()
+        .contains("\"isolated_pois\": {\"count\": 3, \"examples\": [4, 6, 99]}"));
+}
+
+/// Junction at node 1 with roads to the south (0), north (2), east (3) and west (4).
+fn crossing(north_speed_limit: u16) -> Map {
+    let nodes = vec![
+        Node::new(0, 63.00, 10.00),
+        Node::new(1, 63.01, 10.00),
+        Node::new(2, 63.02, 10.00),
+        Node::new(3, 63.01, 10.02),
+        Node::new(4, 63.01, 9.98),
+    ];
+    let mut edges = vec![Vec::new(); 5];
+    for (arm, speed_limit) in [(0, 50), (2, north_speed_limit), (3, 50), (4, 50)] {
+        let drive_time = 1000 * 360 / speed_limit as u32;
+        edges[1].push(EdgeTo::new(arm, drive_time, 1000, speed_limit));
+        edges[arm as usize].push(EdgeTo::new(1, drive_time, 1000, speed_limit));
+    }
+    Map::from_nodes_edges_and_poi(nodes, edges, HashMap::new())
+}
+
+#[test]
+fn route_uses_cheapest_parallel_edge() {
+    let mut map = crossing(50);
+    map.edges[0].insert(0, EdgeTo::new(1, 9000, 1100, 40));
+    map.edges[0].push(EdgeTo::new(1, 6000, 1000, 60));
+
+    let route = Route::from_node_path(&map, &[0, 1, 3]).unwrap();
+    assert_eq!(route.nodes(), vec![0, 1, 3]);
+    assert_eq!(route.edges[0].edge, EdgeTo::new(1, 6000, 1000, 60));
+    assert_eq!(route.drive_time(), 6000 + 7200);
+    assert_eq!(route.length(), 2000);
+    assert_eq!(Route::from_node_path(&map, &[0, 3]), None);
+}
+
+#[test]
+fn turn_by_turn_announces_junctions() {
+    let maneuvers = |map: &Map, path: &[u32]| -> Vec<Maneuver> {
+        let route = Route::from_node_path(map, path).unwrap();
+        turn_by_turn(map, &route)
+            .iter()
+            .map(|i| i.maneuver)
+            .collect()
+    };
+    let map = crossing(50);
+    assert_eq!(
+        maneuvers(&map, &[0, 1, 3]),
+        vec![Maneuver::Depart, Maneuver::TurnRight, Maneuver::Arrive]
+    );
+    assert_eq!(
+        maneuvers(&map, &[0, 1, 4]),
+        vec![Maneuver::Depart, Maneuver::TurnLeft, Maneuver::Arrive]
+    );
+    assert_eq!(
+        maneuvers(&map, &[0, 1, 0]),
+        vec![Maneuver::Depart, Maneuver::UTurn, Maneuver::Arrive]
+    );
+    assert_eq!(
+        maneuvers(&map, &[0, 1, 2]),
+        vec![Maneuver::Depart, Maneuver::Arrive]
+    );
+
+    let map = crossing(80);
+    let route = Route::from_node_path(&map, &[0, 1, 2]).unwrap();
+    let instructions = turn_by_turn(&map, &route);
+    assert_eq!(instructions[1].maneuver, Maneuver::ContinueStraight);
+    assert_eq!(
+        instructions[0].to_text(),
+        "Head north, then drive 1.0 km (50 km/h)"
+    );
+    assert_eq!(instructions[0].length, 1000);
+    assert_eq!(instructions[1].speed_limits, vec![80]);
+}
+
+/// Square grid of two-way roads around Trondheim with `spacing` meters between the nodes.
+fn grid_map(size: usize, spacing: f64) -> Map {
+    let latitude_step = spacing / 111_320.0;
+    let longitude_step = latitude_step / 63.4_f64.to_radians().cos();
+    let nodes: Vec<Node> = (0..size * size)
+        .map(|i| {
+            let (row, column) = ((i / size) as f64, (i % size) as f64);
+            Node::new(
+                i as u32,
+                63.4 + row * latitude_step,
+                10.4 + column * longitude_step,
+            )
+        })
+        .collect();
+    let mut edges = vec![Vec::new(); size * size];
+    for i in 0..size * size {
+        let mut neighbours = Vec::new();
+        if i % size + 1 < size {
+            neighbours.push(i + 1);
+        }
+        if i + size < size * size {
+            neighbours.push(i + size);
+        }
+        for j in neighbours {
+            let (a, b) = (&nodes[i], &nodes[j]);
+            let length =
+                haversine_distance(a.latitude, a.longitude, b.latitude, b.longitude).round() as u32;
+            edges[i].push(EdgeTo::new(j as u32, length * 360 / 50, length, 50));
+            edges[j].push(EdgeTo::new(i as u32, length * 360 / 50, length, 50));
+        }
+    }
+    Map::from_nodes_edges_and_poi(nodes, edges, HashMap::new())
+}
+
+#[test]
+fn grid_index_finds_same_nodes_as_scan() {
+    let mut random = Random::new(40);
+    let map = random_map(&mut random, 2000, 0);
+    let index = GridIndex::new(&map, 500.0);
+    for _ in 0..50 {
+        let latitude = 63.0 + random.below(100_000) as f64 / 100_000.0;
+        let longitude = 10.0 + random.below(200_000) as f64 / 100_000.0;
+        let radius = random.below(3000) as f64;
+        let mut expected: Vec<u32> = (0..map.nodes.len() as u32)
+            .filter(|n| {
+                let node = &map.nodes[*n as usize];
+                haversine_distance(latitude, longitude, node.latitude, node.longitude) <= radius
+            })
+            .collect();
+        let mut found: Vec<u32> = index
+            .nodes_within(&map, latitude, longitude, radius)
+            .iter()
+            .map(|(node, _)| *node)
+            .collect();
+        expected.sort_unstable();
+        found.sort_unstable();
+        assert_eq!(found, expected);
+    }
+}
+
+#[test]
+fn noisy_trace_matches_driven_route() {
+    let size = 12;
+    let map = grid_map(size, 40.0);
+    let index = GridIndex::new(&map, 50.0);
+    // East along the bottom row, then north along column 7
+    let mut route: Vec<u32> = (0..8).collect();
+    route.extend((1..size as u32).map(|row| row * size as u32 + 7));
+
+    let mut random = Random::new(7);
+    let mut trace = Vec::new();
+    for node in route.iter().step_by(2) {
+        let (latitude, longitude) = map.get_coordinates_from_node(*node as usize);
+        let noise = |random: &mut Random| (random.below(100) as f64 - 50.0) / 1_000_000.0;
+        trace.push((
+            latitude + noise(&mut random),
+            longitude + noise(&mut random),
+        ));
+    }
+    trace.push(map.get_coordinates_from_node(*route.last().unwrap() as usize));
+
+    let result = match_trace(&map, &index, &trace, MatchingParameters::default());
+    assert_eq!(result.path, route);
+    for point in &result.points {
+        assert!(point.node.is_some());
+        assert!(point.confidence > 0.5 && point.confidence <= 1.0 + 1e-9);
+    }
+
+    // A fix far away from every road is left out and splits the trace between the fixes on
+    // node 4 and 6, so the path jumps from one part to the next
+    trace.insert(3, (64.0, 11.0));
+    let result = match_trace(&map, &index, &trace, MatchingParameters::default());
+    assert_eq!(result.points[3].node, None);
+    assert_eq!(result.points[3].confidence, 0.0);
+    assert_eq!(result.path, [&route[..5], &route[6..]].concat());
+}
+
+#[test]
+fn imports_osm_fixture() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/tiny.osm");
+    let (map, osm_ids) = import_osm(path).unwrap();
+
+    // The footway and the nodes only used by it or the restaurant building are left out
+    assert_eq!(osm_ids, vec![1, 2, 3, 4, 5]);
+    assert_eq!(map.get_coordinates_from_node(3), (63.4320, 10.3920));
+    let edges: Vec<(usize, u32, u16)> = map
+        .edges
+        .iter()
+        .enumerate()
+        .flat_map(|(from, edges)| edges.iter().map(move |e| (from, e.to, e.speed_limit)))
+        .collect();
+    assert_eq!(
+        edges,
+        vec![
+            (0, 1, 30),
+            (1, 0, 30),
+            (1, 2, 30),
+            (2, 1, 30),
+            (2, 3, 60),
+            (4, 3, 50)
+        ]
+    );
+    let edge = &map.edges[0][0];
+    assert_eq!(edge.length, 111);
+    assert_eq!(edge.drive_time, 111 * 360 / 30);
+
+    assert_eq!(map.points_of_interest.len(), 2);
+    assert_eq!(map.points_of_interest[&2], (2, "Circle K & Co".to_owned()));
+    assert_eq!(map.points_of_interest[&4], (8, "Kafé Løkka".to_owned()));
+}
+
+#[test]
+fn hub_labels_give_exact_distances_and_paths() {
+    let mut random = Random::new(42);
+    for order in [HubOrder::Degree, HubOrder::coverage()] {
+        for _ in 0..5 {
+            let node_count = 30 + random.below(150);
+            let edge_count = node_count * (1 + random.below(3));
+            let map = random_map(&mut random, node_count, edge_count);
+            let built = HubLabels::build(&map, &order.nodes(&map), |_| {});
+            let labels = HubLabels::from_bytes(&built.to_bytes()).unwrap();
+            assert_eq!(labels.to_bytes(), built.to_bytes());
+
+            for source in 0..node_count {
+                let distances = full_dijkstra(&map, source as u32).distances;
+                for (goal, &distance) in distances.iter().enumerate() {
+                    if distance == UNREACHED {
+                        assert!(labels.query(source, goal).is_none());
+                        continue;
+                    }
+                    assert_eq!(labels.query(source, goal).unwrap().drive_time, distance);
+                    let path = labels.path(source, goal).unwrap();
+                    assert_eq!(path.last(), Some(&(goal as u32)));
+                    assert_valid_path(&map, source, &path, distance as usize);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn arc_flags_agree_with_dijkstra() {
+    let mut random = Random::new(43);
+    for (rows, columns) in [(1, 1), (2, 3), (4, 4), (8, 8)] {
+        let node_count = 100 + random.below(300);
+        let edge_count = node_count * (2 + random.below(3));
+        let map = random_map(&mut random, node_count, edge_count);
+        let built = ArcFlags::build(&map, rows, columns, 3, |_, _| {});
+        let arc_flags = ArcFlags::from_bytes(&map, &built.to_bytes()).unwrap();
+        assert_eq!(arc_flags.to_bytes(), built.to_bytes());
+
+        for _ in 0..10 {
+            let source = random.below(node_count);
+            let distances = full_dijkstra(&map, source as u32).distances;
+            for (goal, &distance) in distances.iter().enumerate() {
+                if distance == UNREACHED {
+                    assert_eq!(arc_flags_dijkstra(&map, &arc_flags, source, goal), None);
+                    continue;
+                }
+                let result = arc_flags_dijkstra(&map, &arc_flags, source, goal).unwrap();
+                assert_eq!(result.drive_time, distance);
+                assert_valid_path(&map, source, &result.path, distance as usize);
+            }
+        }
+    }
+}
+
+#[test]
+fn arc_flag_files_with_bad_cells_are_rejected() {
+    let map = Map::load(FIXTURE_PREFIX).unwrap();
+    let bytes = ArcFlags::build(&map, 2, 3, 1, |_, _| {}).to_bytes();
+    assert!(ArcFlags::from_bytes(&map, &bytes).is_some());
+
+    // No cells means no flag bytes, so only the header and the cells of the nodes are left
+    let mut no_cells = bytes[..20 + map.edges.len()].to_vec();
+    no_cells[4..8].copy_from_slice(&0_u32.to_be_bytes());
+    assert!(ArcFlags::from_bytes(&map, &no_cells).is_none());
+
+    for cell in [6, 64, 255] {
+        let mut outside = bytes.clone();
+        outside[20] = cell;
+        assert!(ArcFlags::from_bytes(&map, &outside).is_none());
+    }
+}
+
+#[test]
+fn svg_draws_search_inside_canvas() {
+    let map = grid_map(10, 200.0);
+    let result = closest_dijkstra(&map, 0, 99).unwrap();
+    let (path, visited) = (result.path, result.visited);
+    let bounds = BoundingBox::around(&map, &visited, 0.05).unwrap();
+
+    for projection in [Projection::Equirectangular, Projection::WebMercator] {
+        let svg = SvgMap::new(&map, bounds, projection, 500)
+            .draw_road_network()
+            .draw_visited(&visited)
+            .draw_path(&path, "#111111", "Shortest path")
+            .to_svg();
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        // Every visited node is one dot, plus the start and end of the path
+        assert_eq!(svg.matches("h0").count(), visited.len() + 2);
+
+        let height: f64 = svg
+            .split("height=\"")
+            .nth(1)
+            .unwrap()
+            .split('"')
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        let coordinates: Vec<f64> = svg
+            .split(" d=\"")
+            .skip(1)
+            .flat_map(|d| {
+                let data = d.split('"').next().unwrap().replace("h0", "");
+                data.split(['M', 'L', ' '])
+                    .filter_map(|n| n.parse().ok())
+                    .collect::<Vec<f64>>()
+            })
+            .collect();
+        assert!(!coordinates.is_empty());
+        assert_eq!(coordinates.len() % 2, 0);
+        for pair in coordinates.chunks(2) {
+            assert!((0.0..=500.0).contains(&pair[0]), "x {} outside", pair[0]);
+            assert!((0.0..=height).contains(&pair[1]), "y {} outside", pair[1]);
+        }
+    }
+}
+
+#[test]
+fn raster_keeps_closest_source_per_cell() {
+    let mut random = Random::new(45);
+    let map = random_map(&mut random, 300, 900);
+    let sources = [3, 150, 299];
+    let distances = multi_source_dijkstra(&map, &sources);
+    let single: Vec<Vec<u32>> = sources
+        .iter()
+        .map(|s| full_dijkstra(&map, *s).distances)
+        .collect();
+    for (node, distance) in distances.iter().enumerate() {
+        assert_eq!(*distance, single.iter().map(|d| d[node]).min().unwrap());
+    }
+
+    let raster = TravelTimeRaster::new(&map, &distances, None, 0.05).unwrap();
+    let mut expected = vec![None; raster.rows * raster.columns];
+    for (node, &distance) in distances.iter().enumerate() {
+        if distance == UNREACHED {
+            continue;
+        }
+        let (latitude, longitude) = map.get_coordinates_from_node(node);
+        let row = ((raster.bounds.max_latitude - latitude) / raster.cell_size) as usize;
+        let column = ((longitude - raster.bounds.min_longitude) / raster.cell_size) as usize;
+        let cell: &mut Option<u32> = &mut expected[row * raster.columns + column];
+        *cell = Some(cell.map_or(distance, |c| c.min(distance)));
+    }
+    assert_eq!(raster.cells, expected);
+    assert_eq!(raster.cells.iter().flatten().min(), Some(&0));
+
+    let path = env::temp_dir().join("raster_test.pgm");
+    let path = path.to_str().unwrap();
+    raster.write_pgm(path, raster.max_time()).unwrap();
+    let bytes = fs::read(path).unwrap();
+    let header = format!("P5\n{} {}\n255\n", raster.columns, raster.rows);
+    assert!(bytes.starts_with(header.as_bytes()));
+    assert_eq!(bytes.len(), header.len() + raster.rows * raster.columns);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn durations_format_and_parse() {
+    let duration = TravelDuration::from_centi_seconds((2 * 3600 + 5 * 60 + 3) * 100 + 99);
+    assert_eq!(duration.to_iso8601(), "PT2H5M3S");
+    assert_eq!(duration.to_clock(), "02:05:03");
+    assert_eq!(
+        duration.to_text(Language::English),
+        "2 hours, 5 minutes and 3 seconds."
+    );
+    assert_eq!(
+        duration.to_text(Language::Norwegian),
+        "2 timer, 5 minutter og 3 sekunder."
+    );
+    assert_eq!(
+        centi_seconds_to_time_format(100 * (86_400 + 3600 + 60 + 1)),
+        "1 day, 1 hour, 1 minute and 1 second."
+    );
+    assert_eq!(centi_seconds_to_time_format(4200), "42 seconds.");
+    assert_eq!(TravelDuration::from_seconds(0).to_iso8601(), "PT0S");
+    assert_eq!(TravelDuration::from_seconds(3600).to_iso8601(), "PT1H");
+    assert_eq!(
+        TravelDuration::from_seconds(30 * 3600).to_clock(),
+        "30:00:00"
+    );
+
+    for text in ["PT2H5M3S", "02:05:03"] {
+        assert_eq!(TravelDuration::parse(text).unwrap().seconds(), 7503);
+    }
+    assert_eq!(
+        TravelDuration::parse("PT90M").unwrap().to_iso8601(),
+        "PT1H30M"
+    );
+    assert_eq!(
+        TravelDuration::parse("PT1.5S").unwrap(),
+        TravelDuration::from_centi_seconds(150)
+    );
+    for text in ["PT", "PT5", "P1D", "1:60", "abc"] {
+        assert!(TravelDuration::parse(text).is_none(), "{}", text);
+    }
+}
+
+#[test]
+fn schedules_cross_dates() {
+    let drive = TravelDuration::from_centi_seconds((3 * 3600 + 30 * 60) * 100 + 1);
+    let departure = Timestamp::parse("2024-02-28T22:00+01:00").unwrap();
+    let (_, arrival) = Schedule::DepartAt(departure).times(drive);
+    // Leap day, and the started second is rounded up
+    assert_eq!(arrival.to_iso8601(), "2024-02-29T01:30:01+01:00");
+
+    let arrival = Timestamp::parse("2025-01-01 01:00:00Z").unwrap();
+    let schedule = Schedule::ArriveBy(arrival);
+    let (departure, _) = schedule.times(drive);
+    assert_eq!(departure.to_iso8601(), "2024-12-31T21:29:59Z");
+    assert_eq!(
+        schedule.describe(drive, Language::English),
+        "Leave by 2024-12-31T21:29:59Z to arrive at 2025-01-01T01:00:00Z (PT3H30M)"
+    );
+
+    // Every day of four centuries survives the round trip
+    let start = Timestamp::parse("1900-01-01T00:00").unwrap();
+    let day = TravelDuration::from_seconds(86_400);
+    let mut current = start;
+    for _ in 0..146_097 {
+        let text = current.to_iso8601();
+        assert_eq!(Timestamp::parse(&text).unwrap(), current, "{}", text);
+        current = current.add(day);
+    }
+    assert_eq!(current.to_iso8601(), "2300-01-01T00:00:00");
+
+    for text in [
+        "2023-02-29T10:00",
+        "2024-13-01T10:00",
+        "2024-01-01T24:00",
+        "2024-01-01",
+    ] {
+        assert!(Timestamp::parse(text).is_none(), "{}", text);
+    }
+}
+
+#[test]
+fn vehicle_profiles_never_beat_map_drive_time() {
+    let truck = VehicleProfile::truck();
+    // 1 km at 50 km/h takes 7200 cs, 80 km/h 4500 cs
+    let free = EdgeTo::new(1, 7200, 1000, 50);
+    let slow = EdgeTo::new(1, 9000, 1000, 50);
+    let motorway = EdgeTo::new(1, 6000, 1000, 80);
+
+    assert_eq!(VehicleProfile::car().drive_time(&slow), 9000);
+    assert_eq!(truck.drive_time(&free), 8000);
+    assert_eq!(truck.drive_time(&motorway), 6000);
+    assert_eq!(VehicleProfile::tractor().drive_time(&motorway), 9000);
+    // The truck at 45 km/h is faster than the map says the road can be driven
+    assert_eq!(truck.drive_time(&slow), 9000);
+    assert_eq!(truck.allow_faster_than_map().drive_time(&slow), 8000);
+}
+
+#[test]
+fn changed_profiles_get_their_own_cache_files() {
+    let car = VehicleProfile::car();
+    assert_eq!(car.waypoint_path("norden"), "norden_waypoints.bin");
+    let truck = VehicleProfile::truck();
+    let path = truck.arc_flag_path("norden");
+    assert!(path.starts_with("norden_arc_flags_truck_") && path.ends_with(".bin"));
+    assert_eq!(VehicleProfile::truck().arc_flag_path("norden"), path);
+
+    // Same name, other speeds
+    let changed = [
+        VehicleProfile::new("truck", Some(70), vec![(30, 0.8), (60, 0.9)]),
+        VehicleProfile::new("truck", Some(80), vec![(30, 0.8), (60, 0.8)]),
+        VehicleProfile::new("truck", None, vec![(30, 0.8), (60, 0.9)]),
+        VehicleProfile::truck().allow_faster_than_map(),
+        VehicleProfile::new("car", Some(80), Vec::new()),
+    ];
+    let mut paths: Vec<String> = changed.iter().map(|p| p.waypoint_path("norden")).collect();
+    paths.push(truck.waypoint_path("norden"));
+    paths.push(car.waypoint_path("norden"));
+    let count = paths.len();
+    paths.sort();
+    paths.dedup();
+    assert_eq!(paths.len(), count);
+}
+
+#[test]
+fn graph_views_match_map_copies() {
+    let mut random = Random::new(48);
+    let map = random_map(&mut random, 300, 1000);
+    let truck = VehicleProfile::truck();
+    let truck_map = map.get_profile_copy(&truck);
+    let reverse_map = map.get_reverse_copy();
+    let mut even_map = map.clone();
+    for edges in &mut even_map.edges {
+        edges.retain(|e| e.to % 2 == 0);
+    }
+    let everything = |_, _| ControlFlow::Continue(());
+    let mut search = Search::<QuadHeap<u32>>::new(map.edges.len());
+
+    // The search is reused, so nothing may be left of the previous run
+    for source in [0, 17, 123, 299] {
+        let expected = full_dijkstra(&truck_map, source as u32);
+        search.run(
+            &ProfileWeighted::new(&map, &truck),
+            &[source],
+            |_| 0,
+            everything,
+        );
+        for node in 0..300 {
+            assert_eq!(search.distance(node), expected.distance_to(node));
+        }
+
+        let expected = full_dijkstra(&reverse_map, source as u32);
+        search.run(&Reversed::new(&map), &[source], |_| 0, everything);
+        for node in 0..300 {
+            assert_eq!(search.distance(node), expected.distance_to(node));
+            if let Some(path) = search.path_to(node) {
+                let distance = expected.distances[node] as usize;
+                assert_valid_path(&reverse_map, source, &path, distance);
+            }
+        }
+
+        // Leaving out every edge into odd nodes is the same as deleting them
+        let expected = full_dijkstra(&even_map, source as u32);
+        let view = Filtered::new(&map, |_, edge| {
+            (edge.to % 2 == 0).then_some(edge.drive_time)
+        });
+        search.run(&view, &[source], |_| 0, everything);
+        for node in 0..300 {
+            assert_eq!(search.distance(node), expected.distance_to(node));
+        }
+    }
+}
+
+#[test]
+fn detours_rejoin_the_route_at_the_cheapest_pair_of_nodes() {
+    let mut random = Random::new(49);
+    for _ in 0..20 {
+        let node_count = 50 + random.below(300);
+        let map = random_map(&mut random, node_count, node_count * 3);
+        let reverse = Reversed::new(&map);
+        let reverse_map = map.get_reverse_copy();
+        let (source, goal) = (random.below(node_count), random.below(node_count));
+        let Some(route) = closest_dijkstra(&map, source, goal) else {
+            continue;
+        };
+        let category = 1 << random.below(6);
+        let from_source = full_dijkstra(&map, source as u32);
+        let to_goal = full_dijkstra(&reverse_map, goal as u32);
+        // The route is a shortest path, so every node on it is reached along it
+        let arrivals: Vec<u32> = route
+            .path
+            .iter()
+            .map(|n| from_source.distances[*n as usize])
+            .collect();
+        let from_route: Vec<_> = route.path.iter().map(|n| full_dijkstra(&map, *n)).collect();
+        let to_route: Vec<_> = route
+            .path
+            .iter()
+            .map(|n| full_dijkstra(&reverse_map, *n))
+            .collect();
+
+        let found = detours(&map, &reverse, &route.path, category, UNREACHED, usize::MAX).unwrap();
+        for pair in found.windows(2) {
+            assert!(pair[0].added_time <= pair[1].added_time);
+        }
+        for detour in &found {
+            let poi = detour.point_of_interest;
+            assert_eq!(map.points_of_interest[&poi].0 & category, category);
+            assert!(detour.path.contains(&poi));
+            assert_eq!(detour.path.last(), Some(&(goal as u32)));
+            assert_valid_path(&map, source, &detour.path, detour.drive_time as usize);
+            assert_eq!(detour.added_time, detour.drive_time - route.drive_time);
+            // No route through the stop is faster than driving there and on to the goal
+            let best = from_source.distances[poi as usize] + to_goal.distances[poi as usize];
+            assert!(detour.drive_time >= best);
+            // and no other pair of route nodes to leave and rejoin at gives a faster detour
+            let stop = poi as usize;
+            let leave = (0..arrivals.len()).map(|i| arrivals[i] + from_route[i].distances[stop]);
+            let rejoin = (0..arrivals.len())
+                .map(|i| to_route[i].distances[stop] + route.drive_time - arrivals[i]);
+            assert_eq!(
+                detour.drive_time,
+                leave.min().unwrap() + rejoin.min().unwrap()
+            );
+            if route.path.contains(&poi) {
+                assert_eq!(detour.added_time, 0);
+            }
+        }
+
+        // Without a limit every stop on some way from the source to the goal is found
+        let reachable = map
+            .points_of_interest
+            .iter()
+            .filter(|(node, poi)| {
+                poi.0 & category == category
+                    && from_source.distance_to(**node as usize).is_some()
+                    && to_goal.distance_to(**node as usize).is_some()
+            })
+            .count();
+        assert_eq!(found.len(), reachable);
+
+        let limited = detours(&map, &reverse, &route.path, category, 3000, 3).unwrap();
+        assert!(limited.len() <= 3);
+        for detour in &limited {
+            assert!(found.contains(detour));
+        }
+    }
+
+    // Leaving at the source and rejoining at the goal beats driving the route past the stop and
+    // back, even though the source is closest from the stop and the goal closest to it
+    let mut map = map_from_edges(4, &[(0, 1), (1, 2), (0, 3), (3, 0), (2, 3), (3, 2)]);
+    let drive_times = [
+        (0, 0, 50),
+        (1, 0, 50),
+        (0, 1, 9),
+        (3, 0, 9),
+        (2, 0, 5),
+        (3, 1, 100),
+    ];
+    for (from, edge, drive_time) in drive_times {
+        map.edges[from][edge].drive_time = drive_time;
+    }
+    map.points_of_interest.insert(3, (1, "Stop".to_owned()));
+    let found = detours(&map, &Reversed::new(&map), &[0, 1, 2], 1, UNREACHED, 1).unwrap();
+    assert_eq!(found[0].path, [0, 3, 2]);
+    assert_eq!((found[0].leave, found[0].rejoin), (0, 2));
+    assert_eq!(found[0].added_time, 9);
+}
+
+#[test]
+fn meeting_point_is_best_of_every_candidate() {
+    let mut random = Random::new(50);
+    for _ in 0..20 {
+        let node_count = 50 + random.below(300);
+        let map = random_map(&mut random, node_count, node_count * 3);
+        let travellers: Vec<u32> = (0..2 + random.below(3))
+            .map(|_| random.below(node_count) as u32)
+            .collect();
+        let trees: Vec<_> = travellers.iter().map(|t| full_dijkstra(&map, *t)).collect();
+        let category = 1 << random.below(6);
+
+        for objective in [Objective::Latest, Objective::Total] {
+            for category in [None, Some(category)] {
+                // Lowest cost and node over every node all travellers reach
+                let expected = (0..node_count)
+                    .filter(|node| {
+                        category.is_none_or(|c| {
+                            map.points_of_interest
+                                .get(&(*node as u32))
+                                .is_some_and(|poi| poi.0 & c == c)
+                        })
+                    })
+                    .filter_map(|node| {
+                        let times: Option<Vec<u64>> = trees
+                            .iter()
+                            .map(|t| t.distance_to(node).map(u64::from))
+                            .collect();
+                        let times = times?;
+                        let cost = match objective {
+                            Objective::Latest => *times.iter().max().unwrap(),
+                            Objective::Total => times.iter().sum(),
+                        };
+                        Some((cost, node))
+                    })
+                    .min();
+
+                let meeting = meeting_point(&map, &travellers, objective, category);
+                assert_eq!(
+                    meeting.as_ref().map(|m| (m.cost, m.node as usize)),
+                    expected
+                );
+                let Some(meeting) = meeting else {
+                    continue;
+                };
+                for (i, traveller) in travellers.iter().enumerate() {
+                    let time = meeting.times[i];
+                    assert_eq!(trees[i].distance_to(meeting.node as usize), Some(time));
+                    let path = &meeting.paths[i];
+                    assert_valid_path(&map, *traveller as usize, path, time as usize);
+                    assert_eq!(path.last(), Some(&meeting.node));
+                }
+            }
+        }
+    }
+    assert!(meeting_point(&Map::new(), &[], Objective::Total, None).is_none());
+}
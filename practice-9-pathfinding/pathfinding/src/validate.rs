@@ -0,0 +1,306 @@
+use std::fmt::{Debug, Write};
+
+use crate::components::strongly_connected_components;
+use crate::{format_number, Map};
+
+const MAX_EXAMPLES: usize = 10;
+const DEGREE_BUCKETS: usize = 8;
+/// Relative difference allowed between `drive_time` and `length / speed_limit`.
+const DRIVE_TIME_TOLERANCE: f64 = 0.1;
+/// Absolute difference always allowed, since short edges are rounded to whole centiseconds.
+const DRIVE_TIME_SLACK: f64 = 100.0;
+
+/// Number of occurrences of a problem, with the first few as examples.
+#[derive(Debug, Clone)]
+pub struct Findings<T> {
+    pub count: usize,
+    pub examples: Vec<T>,
+}
+
+impl<T> Findings<T> {
+    fn new() -> Self {
+        Self {
+            count: 0,
+            examples: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, example: T) {
+        self.count += 1;
+        if self.examples.len() < MAX_EXAMPLES {
+            self.examples.push(example);
+        }
+    }
+}
+
+pub struct MapReport {
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub poi_count: usize,
+    /// Number of nodes with out-degree 0, 1, ..., the last bucket holds the rest.
+    pub out_degrees: Vec<usize>,
+    pub in_degrees: Vec<usize>,
+    pub max_out_degree: usize,
+    pub max_in_degree: usize,
+    /// (min latitude, min longitude, max latitude, max longitude)
+    pub bounding_box: Option<(f64, f64, f64, f64)>,
+    /// (from, to) of edges whose end is not in the node list.
+    pub dangling_edges: Findings<(u32, u32)>,
+    pub self_loops: Findings<u32>,
+    /// (from, to) for every edge beyond the first between the same two nodes.
+    pub duplicate_edges: Findings<(u32, u32)>,
+    pub zero_drive_time: Findings<(u32, u32)>,
+    pub zero_speed_limit: Findings<(u32, u32)>,
+    /// (from, to, drive time, drive time expected from length and speed limit)
+    pub inconsistent_drive_time: Findings<(u32, u32, u32, u32)>,
+    pub nodes_without_edges: Findings<u32>,
+    /// Points of interest outside the largest strongly connected component, which can not be
+    /// reached from most of the map or can not get back to it.
+    pub isolated_pois: Findings<u32>,
+}
+
+impl MapReport {
+    pub fn new(map: &Map) -> Self {
+        let node_count = map.nodes.len();
+        let mut report = Self {
+            node_count,
+            edge_count: map.edges.iter().map(Vec::len).sum(),
+            poi_count: map.points_of_interest.len(),
+            out_degrees: vec![0; DEGREE_BUCKETS + 1],
+            in_degrees: vec![0; DEGREE_BUCKETS + 1],
+            max_out_degree: 0,
+            max_in_degree: 0,
+            bounding_box: None,
+            dangling_edges: Findings::new(),
+            self_loops: Findings::new(),
+            duplicate_edges: Findings::new(),
+            zero_drive_time: Findings::new(),
+            zero_speed_limit: Findings::new(),
+            inconsistent_drive_time: Findings::new(),
+            nodes_without_edges: Findings::new(),
+            isolated_pois: Findings::new(),
+        };
+
+        for node in &map.nodes {
+            let (min_lat, min_lon, max_lat, max_lon) = report.bounding_box.unwrap_or((
+                node.latitude,
+                node.longitude,
+                node.latitude,
+                node.longitude,
+            ));
+            report.bounding_box = Some((
+                min_lat.min(node.latitude),
+                min_lon.min(node.longitude),
+                max_lat.max(node.latitude),
+                max_lon.max(node.longitude),
+            ));
+        }
+
+        let mut in_degree = vec![0; node_count];
+        for (from, edges) in map.edges.iter().enumerate() {
+            let from_id = from as u32;
+            let mut targets: Vec<u32> = edges.iter().map(|e| e.to).collect();
+            targets.sort_unstable();
+            for pair in targets.windows(2).filter(|p| p[0] == p[1]) {
+                report.duplicate_edges.add((from_id, pair[0]));
+            }
+
+            for edge in edges {
+                if (edge.to as usize) < node_count {
+                    in_degree[edge.to as usize] += 1;
+                } else {
+                    report.dangling_edges.add((from_id, edge.to));
+                }
+                if edge.to == from_id {
+                    report.self_loops.add(from_id);
+                }
+                if edge.drive_time == 0 {
+                    report.zero_drive_time.add((from_id, edge.to));
+                }
+                if edge.speed_limit == 0 {
+                    report.zero_speed_limit.add((from_id, edge.to));
+                    continue;
+                }
+                // length [m] / (speed [km/h] / 3.6) [m/s] * 100 [cs/s]
+                let expected = edge.length as f64 * 360.0 / edge.speed_limit as f64;
+                let difference = (edge.drive_time as f64 - expected).abs();
+                if difference > DRIVE_TIME_SLACK && difference > expected * DRIVE_TIME_TOLERANCE {
+                    report.inconsistent_drive_time.add((
+                        from_id,
+                        edge.to,
+                        edge.drive_time,
+                        expected.round() as u32,
+                    ));
+                }
+            }
+        }
+
+        for (node, &in_degree) in in_degree.iter().enumerate() {
+            let out_degree = map.edges[node].len();
+            report.out_degrees[out_degree.min(DEGREE_BUCKETS)] += 1;
+            report.in_degrees[in_degree.min(DEGREE_BUCKETS)] += 1;
+            report.max_out_degree = report.max_out_degree.max(out_degree);
+            report.max_in_degree = report.max_in_degree.max(in_degree);
+            if out_degree == 0 && in_degree == 0 {
+                report.nodes_without_edges.add(node as u32);
+            }
+        }
+
+        let (component, sizes) = strongly_connected_components(map);
+        let largest = (0..sizes.len()).max_by_key(|id| sizes[*id]);
+        let mut poi_nodes: Vec<u32> = map.points_of_interest.keys().copied().collect();
+        poi_nodes.sort_unstable();
+        for node in poi_nodes {
+            let index = node as usize;
+            if index >= node_count || Some(component[index] as usize) != largest {
+                report.isolated_pois.add(node);
+            }
+        }
+        report
+    }
+
+    pub fn problem_count(&self) -> usize {
+        self.dangling_edges.count
+            + self.self_loops.count
+            + self.duplicate_edges.count
+            + self.zero_drive_time.count
+            + self.zero_speed_limit.count
+            + self.inconsistent_drive_time.count
+            + self.nodes_without_edges.count
+            + self.isolated_pois.count
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "Nodes: {}", format_number(self.node_count as isize));
+        let _ = writeln!(text, "Edges: {}", format_number(self.edge_count as isize));
+        let _ = writeln!(
+            text,
+            "Points of interest: {}",
+            format_number(self.poi_count as isize)
+        );
+        if let Some((min_lat, min_lon, max_lat, max_lon)) = self.bounding_box {
+            let _ = writeln!(
+                text,
+                "Bounding box: ({}, {}) - ({}, {})",
+                min_lat, min_lon, max_lat, max_lon
+            );
+        }
+        for (name, degrees, max) in [
+            ("Out-degree", &self.out_degrees, self.max_out_degree),
+            ("In-degree", &self.in_degrees, self.max_in_degree),
+        ] {
+            let _ = writeln!(text, "{} (max {}):", name, max);
+            for (degree, count) in degrees.iter().enumerate() {
+                let plus = if degree == DEGREE_BUCKETS { "+" } else { "" };
+                let _ = writeln!(
+                    text,
+                    "  {}{}: {}",
+                    degree,
+                    plus,
+                    format_number(*count as isize)
+                );
+            }
+        }
+
+        let _ = writeln!(
+            text,
+            "Problems found: {}",
+            format_number(self.problem_count() as isize)
+        );
+        write_findings(
+            &mut text,
+            "Edges to non-existent nodes",
+            &self.dangling_edges,
+        );
+        write_findings(&mut text, "Self-loops", &self.self_loops);
+        write_findings(&mut text, "Duplicate parallel edges", &self.duplicate_edges);
+        write_findings(
+            &mut text,
+            "Edges with zero drive time",
+            &self.zero_drive_time,
+        );
+        write_findings(
+            &mut text,
+            "Edges with zero speed limit",
+            &self.zero_speed_limit,
+        );
+        write_findings(
+            &mut text,
+            "Drive times inconsistent with length / speed limit (from, to, actual, expected)",
+            &self.inconsistent_drive_time,
+        );
+        write_findings(&mut text, "Nodes without edges", &self.nodes_without_edges);
+        write_findings(
+            &mut text,
+            "Points of interest outside the largest strongly connected component",
+            &self.isolated_pois,
+        );
+        text
+    }
+
+    pub fn to_json(&self) -> String {
+        let bounding_box = match self.bounding_box {
+            Some((min_lat, min_lon, max_lat, max_lon)) => format!(
+                "{{\"min_latitude\": {}, \"min_longitude\": {}, \"max_latitude\": {}, \"max_longitude\": {}}}",
+                min_lat, min_lon, max_lat, max_lon
+            ),
+            None => "null".to_owned(),
+        };
+        let fields = [
+            ("node_count", self.node_count.to_string()),
+            ("edge_count", self.edge_count.to_string()),
+            ("poi_count", self.poi_count.to_string()),
+            ("out_degrees", format!("{:?}", self.out_degrees)),
+            ("in_degrees", format!("{:?}", self.in_degrees)),
+            ("max_out_degree", self.max_out_degree.to_string()),
+            ("max_in_degree", self.max_in_degree.to_string()),
+            ("bounding_box", bounding_box),
+            ("dangling_edges", findings_to_json(&self.dangling_edges)),
+            ("self_loops", findings_to_json(&self.self_loops)),
+            ("duplicate_edges", findings_to_json(&self.duplicate_edges)),
+            ("zero_drive_time", findings_to_json(&self.zero_drive_time)),
+            ("zero_speed_limit", findings_to_json(&self.zero_speed_limit)),
+            (
+                "inconsistent_drive_time",
+                findings_to_json(&self.inconsistent_drive_time),
+            ),
+            (
+                "nodes_without_edges",
+                findings_to_json(&self.nodes_without_edges),
+            ),
+            ("isolated_pois", findings_to_json(&self.isolated_pois)),
+        ];
+        let body: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| format!("  \"{}\": {}", key, value))
+            .collect();
+        format!("{{\n{}\n}}\n", body.join(",\n"))
+    }
+}
+
+fn write_findings<T: Debug>(text: &mut String, name: &str, findings: &Findings<T>) {
+    let _ = writeln!(text, "{}: {}", name, format_number(findings.count as isize));
+    if !findings.examples.is_empty() {
+        let examples: Vec<String> = findings
+            .examples
+            .iter()
+            .map(|e| format!("{:?}", e))
+            .collect();
+        let _ = writeln!(text, "  e.g. {}", examples.join(", "));
+    }
+}
+
+/// Tuples are written as JSON arrays, which is what `{:?}` gives after swapping the brackets.
+fn findings_to_json<T: Debug>(findings: &Findings<T>) -> String {
+    let examples: Vec<String> = findings
+        .examples
+        .iter()
+        .map(|e| format!("{:?}", e).replace('(', "[").replace(')', "]"))
+        .collect();
+    format!(
+        "{{\"count\": {}, \"examples\": [{}]}}",
+        findings.count,
+        examples.join(", ")
+    )
+}
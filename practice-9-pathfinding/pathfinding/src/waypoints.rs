@@ -0,0 +1,252 @@
+use std::ops::ControlFlow;
+
+use crate::components::strongly_connected_components;
+use crate::graph::Search;
+use crate::queue::DefaultQueue;
+use crate::{get_byte_array_from_u32, get_u32_from_byte_array, Map, UNREACHED};
+
+/// Marks the waypoint files written by `create_waypoints`. Files without it are from before the
+/// compact layout and are created again.
+const FILE_MAGIC: &[u8; 4] = b"WPT2";
+const HEADER_LENGTH: usize = 16;
+/// Stored for unreached nodes in quantised files, every reached node is below it.
+const UNREACHED_QUANTISED: u16 = u16::MAX;
+
+enum Distances {
+    Exact(Vec<u32>),
+    /// Distances divided by the quantum of their landmark, rounded down.
+    Quantised(Vec<u16>),
+}
+
+/// Landmark distances for ALT, interleaved per node. The values of node `n` are
+/// `[to_0, from_0, to_1, from_1, ...]` from index `n * 2 * landmarks`, so a lower bound reads
+/// two short runs instead of one value from each of the landmark arrays.
+///
+/// Files are read whole into memory. Mapping them instead would need a dependency on libc or
+/// memmap, which this crate does without.
+pub struct Waypoints {
+    sources: Vec<u32>,
+    /// Centiseconds per stored unit for each landmark, 1 when the distances are exact.
+    quanta: Vec<u32>,
+    node_count: usize,
+    distances: Distances,
+}
+
+impl Waypoints {
+    pub fn sources(&self) -> &[u32] {
+        &self.sources
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    pub fn is_quantised(&self) -> bool {
+        matches!(self.distances, Distances::Quantised(_))
+    }
+
+    /// Lower bound on the drive time from `node` to `goal`.
+    ///
+    /// A quantised distance can be up to one quantum too low, so the difference of two of them
+    /// can be one quantum too high. One quantum is subtracted to keep the bound admissible.
+    pub fn lower_bound(&self, node: usize, goal: usize) -> u32 {
+        let width = self.sources.len() * 2;
+        let mut bound = 0;
+        match &self.distances {
+            Distances::Exact(distances) => {
+                let at_node = &distances[node * width..(node + 1) * width];
+                let at_goal = &distances[goal * width..(goal + 1) * width];
+                for i in 0..self.sources.len() {
+                    bound = bound
+                        .max(at_goal[2 * i].saturating_sub(at_node[2 * i]))
+                        .max(at_node[2 * i + 1].saturating_sub(at_goal[2 * i + 1]));
+                }
+            }
+            Distances::Quantised(distances) => {
+                let at_node = &distances[node * width..(node + 1) * width];
+                let at_goal = &distances[goal * width..(goal + 1) * width];
+                for (i, quantum) in self.quanta.iter().enumerate() {
+                    let to = (at_goal[2 * i] as u32).saturating_sub(at_node[2 * i] as u32 + 1);
+                    let from =
+                        (at_node[2 * i + 1] as u32).saturating_sub(at_goal[2 * i + 1] as u32 + 1);
+                    bound = bound.max(to.max(from) * quantum);
+                }
+            }
+        }
+        bound
+    }
+
+    /// Magic, quantised flag, landmark count and node count, followed by `landmark_count`
+    /// blocks from `landmark_bytes` in any order.
+    pub fn header_bytes(landmark_count: usize, node_count: usize, quantised: bool) -> Vec<u8> {
+        let mut bytes = FILE_MAGIC.to_vec();
+        bytes.extend(get_byte_array_from_u32(quantised as u32));
+        bytes.extend(get_byte_array_from_u32(landmark_count as u32));
+        bytes.extend(get_byte_array_from_u32(node_count as u32));
+        bytes
+    }
+
+    /// Source, quantum, then the distances to and from the source, big-endian like the rest of
+    /// the file. Quantised distances take two bytes each, exact ones four.
+    pub fn landmark_bytes(
+        source: u32,
+        distances_to: &[u32],
+        distances_from: &[u32],
+        quantised: bool,
+    ) -> Vec<u8> {
+        let longest = distances_to
+            .iter()
+            .chain(distances_from)
+            .filter(|d| **d < UNREACHED)
+            .max()
+            .copied()
+            .unwrap_or(0);
+        let quantum = if quantised {
+            longest / (UNREACHED_QUANTISED as u32) + 1
+        } else {
+            1
+        };
+
+        let value_size = if quantised { 2 } else { 4 };
+        let mut bytes = Vec::with_capacity(8 + (distances_to.len() * 2) * value_size);
+        bytes.extend(get_byte_array_from_u32(source));
+        bytes.extend(get_byte_array_from_u32(quantum));
+        for &distance in distances_to.iter().chain(distances_from) {
+            if !quantised {
+                bytes.extend(get_byte_array_from_u32(distance));
+            } else if distance >= UNREACHED {
+                bytes.extend(UNREACHED_QUANTISED.to_be_bytes());
+            } else {
+                bytes.extend(((distance / quantum) as u16).to_be_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Returns None if the bytes are not a complete waypoint file.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < HEADER_LENGTH || &bytes[0..4] != FILE_MAGIC {
+            return None;
+        }
+        let quantised = get_u32_from_byte_array(&bytes[4..8]) != 0;
+        let landmark_count = get_u32_from_byte_array(&bytes[8..12]) as usize;
+        let node_count = get_u32_from_byte_array(&bytes[12..16]) as usize;
+        let value_size = if quantised { 2 } else { 4 };
+        let block_length = 8 + node_count * 2 * value_size;
+        if bytes.len() != HEADER_LENGTH + landmark_count * block_length {
+            return None;
+        }
+
+        let width = landmark_count * 2;
+        let mut sources = Vec::with_capacity(landmark_count);
+        let mut quanta = Vec::with_capacity(landmark_count);
+        let mut exact = Vec::new();
+        let mut compact = Vec::new();
+        if quantised {
+            compact = vec![0; node_count * width];
+        } else {
+            exact = vec![0; node_count * width];
+        }
+
+        let blocks = bytes[HEADER_LENGTH..].chunks_exact(block_length);
+        for (i, block) in blocks.enumerate() {
+            sources.push(get_u32_from_byte_array(&block[0..4]));
+            quanta.push(get_u32_from_byte_array(&block[4..8]));
+            // Distances to the source fill column 2i, distances from it column 2i + 1
+            let values = block[8..].chunks_exact(value_size);
+            for (j, value) in values.enumerate() {
+                let index = (j % node_count) * width + 2 * i + j / node_count;
+                if quantised {
+                    compact[index] = u16::from_be_bytes([value[0], value[1]]);
+                } else {
+                    exact[index] = get_u32_from_byte_array(value);
+                }
+            }
+        }
+
+        Some(Self {
+            sources,
+            quanta,
+            node_count,
+            distances: if quantised {
+                Distances::Quantised(compact)
+            } else {
+                Distances::Exact(exact)
+            },
+        })
+    }
+}
+
+/// How `get_waypoints` picks the landmarks of a new waypoint file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Landmarks {
+    /// These node numbers, in this order. A file with other landmarks is created again.
+    Nodes(Vec<u32>),
+    /// This many nodes from `farthest_landmarks`.
+    Farthest(usize),
+}
+
+impl Landmarks {
+    /// A count for `Farthest`, or a comma separated list of node numbers.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if !name.contains(',') {
+            return name.trim().parse().ok().map(Self::Farthest);
+        }
+        let nodes: Option<Vec<u32>> = name.split(',').map(|n| n.trim().parse().ok()).collect();
+        nodes.map(Self::Nodes)
+    }
+
+    /// Four landmarks, which the command line uses unless told otherwise.
+    pub fn farthest() -> Self {
+        Self::Farthest(4)
+    }
+
+    /// Node numbers of the landmarks on `map`.
+    pub fn nodes(&self, map: &Map) -> Vec<u32> {
+        match self {
+            Self::Nodes(nodes) => nodes.clone(),
+            Self::Farthest(count) => farthest_landmarks(map, *count),
+        }
+    }
+}
+
+/// `count` landmarks spread over the largest strongly connected component of the map, picked
+/// one at a time as the node farthest by drive time from the ones picked so far. The first is
+/// the one farthest from the lowest node of the component. Landmarks on the edge of the map
+/// give the tightest bounds for the queries crossing it.
+pub fn farthest_landmarks(map: &Map, count: usize) -> Vec<u32> {
+    let (component, sizes) = strongly_connected_components(map);
+    let Some(largest) = (0..sizes.len()).max_by_key(|id| sizes[*id]) else {
+        return Vec::new();
+    };
+    let in_largest = |node: usize| component[node] == largest as u32;
+    let start = (0..component.len()).find(|n| in_largest(*n)).unwrap();
+
+    let mut landmarks: Vec<usize> = Vec::with_capacity(count);
+    let mut search = Search::<DefaultQueue>::new(map.edges.len());
+    while landmarks.len() < count.min(sizes[largest]) {
+        let sources = if landmarks.is_empty() {
+            vec![start]
+        } else {
+            landmarks.clone()
+        };
+        let mut farthest = None;
+        search.run(
+            map,
+            &sources,
+            |_| 0,
+            |node, _| {
+                if in_largest(node) && !landmarks.contains(&node) {
+                    farthest = Some(node);
+                }
+                ControlFlow::Continue(())
+            },
+        );
+        // Nodes are settled by distance, so the last one seen is the farthest
+        let Some(node) = farthest else {
+            break;
+        };
+        landmarks.push(node);
+    }
+    landmarks.into_iter().map(|n| n as u32).collect()
+}